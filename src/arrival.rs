@@ -0,0 +1,163 @@
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+use chrono_tz::Europe::Warsaw;
+use once_cell::sync::Lazy;
+use rand::prelude::ThreadRng;
+use rand::Rng;
+
+use crate::calendar::polish_public_holiday;
+use crate::string_occurrences::parse_column;
+
+const SECONDS_PER_HOUR: f64 = 3600.0;
+const HOURS_PER_DAY: usize = 24;
+const DAYS_PER_WEEK: usize = 7;
+const MONTHS_PER_YEAR: usize = 12;
+// Average of two reports per hour, enough for the units to be saturated at peak times
+const BASE_REPORTS_PER_HOUR: f64 = 2.0;
+// Multipliers for each hour of the day, starting at midnight
+const HOURLY_PROFILE_STRING: &str = include_str!("../data/hourly_report_profile.txt");
+// Multipliers for each day of the week, starting on Monday
+const WEEKDAY_PROFILE_STRING: &str = include_str!("../data/weekday_report_profile.txt");
+// Multipliers for each month, starting in January
+const MONTHLY_PROFILE_STRING: &str = include_str!("../data/monthly_report_profile.txt");
+// Multipliers for each public holiday
+const HOLIDAY_MULTIPLIERS_STRING: &str = include_str!("../data/holiday_report_multipliers.txt");
+
+static HOURLY_PROFILE: Lazy<Vec<f64>> =
+    Lazy::new(|| create_profile_from_str(HOURLY_PROFILE_STRING, HOURS_PER_DAY));
+static WEEKDAY_PROFILE: Lazy<Vec<f64>> =
+    Lazy::new(|| create_profile_from_str(WEEKDAY_PROFILE_STRING, DAYS_PER_WEEK));
+static MONTHLY_PROFILE: Lazy<Vec<f64>> =
+    Lazy::new(|| create_profile_from_str(MONTHLY_PROFILE_STRING, MONTHS_PER_YEAR));
+static HOLIDAY_MULTIPLIERS: Lazy<Vec<(&str, f64)>> =
+    Lazy::new(|| create_holiday_multipliers_from_str(HOLIDAY_MULTIPLIERS_STRING));
+
+/// The expected number of reports per hour at the given moment, following the local time.
+pub fn report_rate_per_hour(time: DateTime<Utc>) -> f64 {
+    let local_time = time.with_timezone(&Warsaw);
+    let hour_multiplier = HOURLY_PROFILE[local_time.hour() as usize];
+    let weekday_multiplier = WEEKDAY_PROFILE[local_time.weekday().num_days_from_monday() as usize];
+    let month_multiplier = MONTHLY_PROFILE[local_time.month0() as usize];
+    let holiday_multiplier = match polish_public_holiday(local_time.date_naive()) {
+        Some(holiday_name) => get_holiday_multiplier(holiday_name),
+        None => 1.0,
+    };
+
+    BASE_REPORTS_PER_HOUR
+        * hour_multiplier
+        * weekday_multiplier
+        * month_multiplier
+        * holiday_multiplier
+}
+
+/// Draws the time of the next report from a non-homogeneous Poisson process using thinning:
+/// candidates are drawn at the highest possible rate and accepted proportionally to the rate at
+/// the candidate's time.
+pub fn generate_next_report_time(
+    generator: &mut ThreadRng,
+    current_time: DateTime<Utc>,
+) -> DateTime<Utc> {
+    let max_rate = BASE_REPORTS_PER_HOUR
+        * max_multiplier(&HOURLY_PROFILE)
+        * max_multiplier(&WEEKDAY_PROFILE)
+        * max_multiplier(&MONTHLY_PROFILE)
+        * HOLIDAY_MULTIPLIERS
+            .iter()
+            .map(|(_, multiplier)| *multiplier)
+            .fold(1.0, f64::max);
+
+    let mut time = current_time;
+    loop {
+        let uniform: f64 = generator.gen();
+        let hours_until_candidate = -(1.0 - uniform).ln() / max_rate;
+        let seconds_until_candidate = (hours_until_candidate * SECONDS_PER_HOUR).ceil() as i64;
+        time = time
+            .checked_add_signed(Duration::seconds(seconds_until_candidate))
+            .unwrap();
+
+        if generator.gen_bool(report_rate_per_hour(time) / max_rate) {
+            return time;
+        }
+    }
+}
+
+fn get_holiday_multiplier(holiday_name: &str) -> f64 {
+    let Some((_, multiplier)) = HOLIDAY_MULTIPLIERS
+        .iter()
+        .find(|(name, _)| *name == holiday_name)
+    else {
+        panic!(
+            "Every holiday should have its multiplier defined. Missing multiplier for \"{}\"",
+            holiday_name
+        );
+    };
+    *multiplier
+}
+
+fn max_multiplier(profile: &[f64]) -> f64 {
+    profile.iter().copied().fold(0.0, f64::max)
+}
+
+/// One multiplier per line.
+fn create_profile_from_str(data: &'static str, length: usize) -> Vec<f64> {
+    let profile = data
+        .split('\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| parse_column(line, line))
+        .collect::<Vec<_>>();
+    if profile.len() != length {
+        panic!(
+            "A profile should have {} multipliers. Found {} in data:\n{}",
+            length,
+            profile.len(),
+            data
+        );
+    }
+    profile
+}
+
+/// Each line contains the holiday name and its multiplier.
+fn create_holiday_multipliers_from_str(data: &'static str) -> Vec<(&'static str, f64)> {
+    data.split('\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let Some((name, multiplier)) = line.split_once(',') else {
+                panic!(
+                    "Each holiday multiplier line should have 2 columns. Found line \"{}\"",
+                    line
+                );
+            };
+            (name, parse_column(multiplier, line))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use crate::arrival::{get_holiday_multiplier, report_rate_per_hour};
+
+    #[test]
+    fn should_have_more_reports_on_friday_evening_than_on_monday_night() {
+        let monday_night = Utc.with_ymd_and_hms(2023, 6, 5, 4, 0, 0).unwrap();
+        let friday_evening = Utc.with_ymd_and_hms(2023, 6, 9, 19, 0, 0).unwrap();
+        assert!(report_rate_per_hour(friday_evening) > report_rate_per_hour(monday_night));
+    }
+
+    #[test]
+    fn should_follow_the_local_new_year_rather_than_the_utc_one() {
+        // 00:30 on Sunday in Warsaw, on the New Year and a week later
+        let new_year = Utc.with_ymd_and_hms(2022, 12, 31, 23, 30, 0).unwrap();
+        let next_sunday = new_year + Duration::weeks(1);
+        let ratio = report_rate_per_hour(new_year) / report_rate_per_hour(next_sunday);
+        assert!((ratio - get_holiday_multiplier("NOWY ROK")).abs() < 1e-9);
+    }
+
+    #[test]
+    fn should_have_a_multiplier_for_every_holiday() {
+        let time = Utc.with_ymd_and_hms(2023, 1, 1, 12, 0, 0).unwrap();
+        for day in 0..365 {
+            report_rate_per_hour(time + Duration::days(day));
+        }
+    }
+}
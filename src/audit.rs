@@ -0,0 +1,55 @@
+use chrono::{DateTime, Utc};
+use rand::prelude::ThreadRng;
+use rand::Rng;
+
+// The operators entering the data into the source systems
+const OPERATORS_COUNT: u32 = 12;
+
+/// The technical columns of a source system table.
+#[derive(Debug, Copy, Clone)]
+pub struct Audit {
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// The id of the operator who entered the row.
+    pub created_by: u32,
+}
+
+impl Audit {
+    pub fn generate_with_time(generator: &mut ThreadRng, time: DateTime<Utc>) -> Self {
+        Self {
+            created_at: time,
+            updated_at: time,
+            created_by: generate_operator_id(generator),
+        }
+    }
+
+    pub fn touch(&mut self, time: DateTime<Utc>) {
+        self.updated_at = self.updated_at.max(time);
+    }
+}
+
+pub fn generate_operator_id(generator: &mut ThreadRng) -> u32 {
+    generator.gen_range(1..=OPERATORS_COUNT)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use rand::thread_rng;
+
+    use crate::audit::Audit;
+
+    #[test]
+    fn should_never_move_updated_at_backwards() {
+        let time = Utc.with_ymd_and_hms(2023, 6, 5, 12, 0, 0).unwrap();
+        let mut audit = Audit::generate_with_time(&mut thread_rng(), time);
+
+        audit.touch(time - Duration::hours(1));
+        assert_eq!(audit.updated_at, time);
+        audit.touch(time + Duration::hours(1));
+        assert_eq!(audit.updated_at, time + Duration::hours(1));
+        audit.touch(time);
+        assert_eq!(audit.updated_at, time + Duration::hours(1));
+        assert_eq!(audit.created_at, time);
+    }
+}
@@ -0,0 +1,232 @@
+use chrono::{DateTime, Datelike, Days, NaiveDate, Utc, Weekday};
+
+pub const DAY_SHIFT_START_HOUR: u32 = 7;
+pub const NIGHT_SHIFT_START_HOUR: u32 = 19;
+const FIRST_EIGHT_HOUR_SHIFT_START_HOUR: u32 = 6;
+const SECOND_EIGHT_HOUR_SHIFT_START_HOUR: u32 = 14;
+const THIRD_EIGHT_HOUR_SHIFT_START_HOUR: u32 = 22;
+// Christmas Eve is a public holiday since 2025
+const FIRST_CHRISTMAS_EVE_HOLIDAY_YEAR: i32 = 2025;
+
+#[derive(Debug, Copy, Clone)]
+pub struct CalendarDay {
+    pub key: u32,
+    pub date: NaiveDate,
+    pub day_of_week: u32,
+    pub day_name: &'static str,
+    pub week_of_year: u32,
+    pub iso_week: u32,
+    pub iso_year: i32,
+    pub month_name: &'static str,
+    pub quarter: u32,
+    pub is_weekend: bool,
+    pub holiday_name: Option<&'static str>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct TimeOfDay {
+    pub key: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub twelve_hour_shift: &'static str,
+    pub eight_hour_shift: &'static str,
+    pub part_of_day: &'static str,
+}
+
+#[derive(Debug, Clone)]
+pub struct Calendar {
+    pub days: Vec<CalendarDay>,
+    pub times: Vec<TimeOfDay>,
+}
+
+impl Calendar {
+    pub fn from_range(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        let mut days = vec![];
+        let mut date = start.date_naive();
+        while date <= end.date_naive() {
+            days.push(CalendarDay::from_date(date));
+            date = date.checked_add_days(Days::new(1)).unwrap();
+        }
+
+        let times = (0..24)
+            .flat_map(|hour| {
+                (0..60).map(move |minute| TimeOfDay::from_hour_and_minute(hour, minute))
+            })
+            .collect();
+
+        Self { days, times }
+    }
+}
+
+impl CalendarDay {
+    pub fn from_date(date: NaiveDate) -> Self {
+        let iso_week = date.iso_week();
+        Self {
+            key: date_to_key(date),
+            date,
+            day_of_week: date.weekday().number_from_monday(),
+            day_name: weekday_to_polish_name(date.weekday()),
+            week_of_year: date.ordinal0() / 7 + 1,
+            iso_week: iso_week.week(),
+            iso_year: iso_week.year(),
+            month_name: month_to_polish_name(date.month()),
+            quarter: (date.month() - 1) / 3 + 1,
+            is_weekend: matches!(date.weekday(), Weekday::Sat | Weekday::Sun),
+            holiday_name: polish_public_holiday(date),
+        }
+    }
+}
+
+impl TimeOfDay {
+    pub fn from_hour_and_minute(hour: u32, minute: u32) -> Self {
+        let twelve_hour_shift = if (DAY_SHIFT_START_HOUR..NIGHT_SHIFT_START_HOUR).contains(&hour) {
+            "DZIENNA"
+        } else {
+            "NOCNA"
+        };
+        let eight_hour_shift = if (FIRST_EIGHT_HOUR_SHIFT_START_HOUR
+            ..SECOND_EIGHT_HOUR_SHIFT_START_HOUR)
+            .contains(&hour)
+        {
+            "I"
+        } else if (SECOND_EIGHT_HOUR_SHIFT_START_HOUR..THIRD_EIGHT_HOUR_SHIFT_START_HOUR)
+            .contains(&hour)
+        {
+            "II"
+        } else {
+            "III"
+        };
+        let part_of_day = match hour {
+            0..=5 => "NOC",
+            6..=9 => "RANO",
+            10..=11 => "PRZEDPOLUDNIE",
+            12..=17 => "POPOLUDNIE",
+            18..=21 => "WIECZOR",
+            _ => "NOC",
+        };
+
+        Self {
+            key: hour * 100 + minute,
+            hour,
+            minute,
+            twelve_hour_shift,
+            eight_hour_shift,
+            part_of_day,
+        }
+    }
+}
+
+pub fn date_to_key(date: NaiveDate) -> u32 {
+    date.year() as u32 * 10000 + date.month() * 100 + date.day()
+}
+
+pub fn polish_public_holiday(date: NaiveDate) -> Option<&'static str> {
+    let name = match (date.month(), date.day()) {
+        (1, 1) => Some("NOWY ROK"),
+        (1, 6) => Some("TRZECH KROLI"),
+        (5, 1) => Some("SWIETO PRACY"),
+        (5, 3) => Some("SWIETO KONSTYTUCJI 3 MAJA"),
+        (8, 15) => Some("WNIEBOWZIECIE NAJSWIETSZEJ MARYI PANNY"),
+        (11, 1) => Some("WSZYSTKICH SWIETYCH"),
+        (11, 11) => Some("NARODOWE SWIETO NIEPODLEGLOSCI"),
+        (12, 24) if date.year() >= FIRST_CHRISTMAS_EVE_HOLIDAY_YEAR => Some("WIGILIA"),
+        (12, 25) => Some("BOZE NARODZENIE"),
+        (12, 26) => Some("DRUGI DZIEN BOZEGO NARODZENIA"),
+        _ => None,
+    };
+    if name.is_some() {
+        return name;
+    }
+
+    let easter = easter_sunday(date.year());
+    match (date - easter).num_days() {
+        0 => Some("WIELKANOC"),
+        1 => Some("PONIEDZIALEK WIELKANOCNY"),
+        49 => Some("ZIELONE SWIATKI"),
+        60 => Some("BOZE CIALO"),
+        _ => None,
+    }
+}
+
+/// Anonymous Gregorian algorithm (Meeus/Jones/Butcher).
+fn easter_sunday(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap()
+}
+
+fn weekday_to_polish_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "PONIEDZIALEK",
+        Weekday::Tue => "WTOREK",
+        Weekday::Wed => "SRODA",
+        Weekday::Thu => "CZWARTEK",
+        Weekday::Fri => "PIATEK",
+        Weekday::Sat => "SOBOTA",
+        Weekday::Sun => "NIEDZIELA",
+    }
+}
+
+fn month_to_polish_name(month: u32) -> &'static str {
+    match month {
+        1 => "STYCZEN",
+        2 => "LUTY",
+        3 => "MARZEC",
+        4 => "KWIECIEN",
+        5 => "MAJ",
+        6 => "CZERWIEC",
+        7 => "LIPIEC",
+        8 => "SIERPIEN",
+        9 => "WRZESIEN",
+        10 => "PAZDZIERNIK",
+        11 => "LISTOPAD",
+        12 => "GRUDZIEN",
+        _ => panic!(
+            "A month number should always be between 1 and 12. Found {}",
+            month
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::calendar::{easter_sunday, polish_public_holiday};
+
+    #[test]
+    fn should_compute_easter_sunday() {
+        assert_eq!(
+            easter_sunday(2015),
+            NaiveDate::from_ymd_opt(2015, 4, 5).unwrap()
+        );
+        assert_eq!(
+            easter_sunday(2019),
+            NaiveDate::from_ymd_opt(2019, 4, 21).unwrap()
+        );
+        assert_eq!(
+            easter_sunday(2023),
+            NaiveDate::from_ymd_opt(2023, 4, 9).unwrap()
+        );
+    }
+
+    #[test]
+    fn should_recognize_movable_holidays() {
+        let corpus_christi = NaiveDate::from_ymd_opt(2023, 6, 8).unwrap();
+        let ordinary_day = NaiveDate::from_ymd_opt(2023, 6, 9).unwrap();
+        assert_eq!(polish_public_holiday(corpus_christi), Some("BOZE CIALO"));
+        assert_eq!(polish_public_holiday(ordinary_day), None);
+    }
+}
@@ -0,0 +1,84 @@
+use chrono::{DateTime, Utc};
+use rand::prelude::{SliceRandom, ThreadRng};
+use rand::Rng;
+
+use crate::person::Person;
+
+// Roughly a third of the reports come from people who have already called before. Repeat callers
+// are picked proportionally to their previous reports, so a few of them call very often
+const REPEAT_REPORTER_CHANCE: f64 = 0.3;
+// Recidivism among the offenders the patrols deal with
+const REPEAT_OFFENDER_CHANCE: f64 = 0.25;
+
+/// Every citizen who has ever made a report or was involved in an intervention, indexed by the citizen's id.
+#[derive(Debug, Clone, Default)]
+pub struct CitizenRegistry {
+    citizens: Vec<Person>,
+    /// The reporter of every report so far.
+    reporter_ids: Vec<usize>,
+    /// Every offender of every intervention so far.
+    offender_ids: Vec<usize>,
+}
+
+impl CitizenRegistry {
+    /// Either a previous reporter or a citizen calling for the first time.
+    pub fn choose_reporter(&mut self, generator: &mut ThreadRng, time: DateTime<Utc>) -> usize {
+        let reporter_id = match self.reporter_ids.choose(generator) {
+            Some(reporter_id) if generator.gen_bool(REPEAT_REPORTER_CHANCE) => *reporter_id,
+            _ => self.add_citizen(generator, time),
+        };
+        self.reporter_ids.push(reporter_id);
+        reporter_id
+    }
+
+    /// Either a previous offender or a citizen involved in an intervention for the first time.
+    pub fn choose_offender(&mut self, generator: &mut ThreadRng, time: DateTime<Utc>) -> usize {
+        let offender_id = match self.offender_ids.choose(generator) {
+            Some(offender_id) if generator.gen_bool(REPEAT_OFFENDER_CHANCE) => *offender_id,
+            _ => self.add_citizen(generator, time),
+        };
+        self.offender_ids.push(offender_id);
+        offender_id
+    }
+
+    pub fn add_citizen(&mut self, generator: &mut ThreadRng, time: DateTime<Utc>) -> usize {
+        let id = self.citizens.len();
+        self.citizens
+            .push(Person::generate_citizen_with_id(generator, time, id));
+        id
+    }
+
+    pub fn get(&self, id: usize) -> &Person {
+        self.citizens.get(id).unwrap()
+    }
+
+    pub fn citizens(&self) -> &[Person] {
+        &self.citizens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use rand::thread_rng;
+
+    use crate::citizen::CitizenRegistry;
+
+    #[test]
+    fn should_reuse_previous_reporters() {
+        let mut generator = thread_rng();
+        let time = Utc.with_ymd_and_hms(2023, 6, 5, 12, 0, 0).unwrap();
+        let mut registry = CitizenRegistry::default();
+        let reports_count = 1000;
+        for _ in 0..reports_count {
+            registry.choose_reporter(&mut generator, time);
+        }
+
+        assert!(registry.citizens().len() < reports_count);
+        assert!(registry
+            .citizens()
+            .iter()
+            .enumerate()
+            .all(|(index, citizen)| citizen.id == index));
+    }
+}
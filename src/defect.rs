@@ -0,0 +1,336 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use rand::prelude::{SliceRandom, StdRng};
+use rand::{Rng, SeedableRng};
+
+// Orphan foreign keys point this far beyond any existing id
+const ORPHAN_FOREIGN_KEY_OFFSET: u64 = 1_000_000;
+const OUT_OF_RANGE_YEARS: [&str; 3] = ["1899", "2099", "0000"];
+
+/// The chance of each defect being injected into a row, if the row has a suitable column.
+#[derive(Debug, Copy, Clone)]
+pub struct DefectRates {
+    pub duplicated_row: f64,
+    pub missing_field: f64,
+    pub typo: f64,
+    pub swapped_names: f64,
+    pub malformed_pesel: f64,
+    pub malformed_phone_number: f64,
+    pub out_of_range_date: f64,
+    pub orphan_foreign_key: f64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DefectKind {
+    DuplicatedRow,
+    MissingField,
+    Typo,
+    SwappedNames,
+    MalformedPesel,
+    MalformedPhoneNumber,
+    OutOfRangeDate,
+    OrphanForeignKey,
+}
+
+/// Which columns of an exported file can receive which defects.
+#[derive(Debug, Copy, Clone)]
+pub struct DefectColumns {
+    pub mandatory: &'static [usize],
+    pub typos: &'static [usize],
+    /// The first and last name columns.
+    pub names: Option<(usize, usize)>,
+    pub pesel: Option<usize>,
+    pub phone_number: Option<usize>,
+    pub dates: &'static [usize],
+    pub foreign_keys: &'static [usize],
+}
+
+#[derive(Debug, Clone)]
+pub struct InjectedDefect {
+    pub file_name: &'static str,
+    /// The 1-based line of the file, counting the injected duplicates.
+    pub row: usize,
+    /// None for defects affecting the whole row.
+    pub column: Option<usize>,
+    pub kind: DefectKind,
+    pub original_value: String,
+}
+
+/// Damages exported rows and keeps track of every defect, so that cleansing rules can be scored.
+/// The defects of a row depend only on the seed and the row's key, so an entity exported unchanged
+/// in several snapshots is damaged the same way in each of them.
+#[derive(Debug, Clone)]
+pub struct DefectInjector {
+    rates: DefectRates,
+    seed: u64,
+    pub defects: Vec<InjectedDefect>,
+}
+
+impl DefectKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            DefectKind::DuplicatedRow => "DUPLICATED_ROW",
+            DefectKind::MissingField => "MISSING_FIELD",
+            DefectKind::Typo => "TYPO",
+            DefectKind::SwappedNames => "SWAPPED_NAMES",
+            DefectKind::MalformedPesel => "MALFORMED_PESEL",
+            DefectKind::MalformedPhoneNumber => "MALFORMED_PHONE_NUMBER",
+            DefectKind::OutOfRangeDate => "OUT_OF_RANGE_DATE",
+            DefectKind::OrphanForeignKey => "ORPHAN_FOREIGN_KEY",
+        }
+    }
+}
+
+impl DefectInjector {
+    pub fn new(rates: DefectRates, seed: u64) -> Self {
+        Self {
+            rates,
+            seed,
+            defects: vec![],
+        }
+    }
+
+    pub fn inject(
+        &mut self,
+        file_name: &'static str,
+        columns: &DefectColumns,
+        key_columns: &[usize],
+        rows: Vec<Vec<String>>,
+    ) -> Vec<Vec<String>> {
+        let mut output = Vec::with_capacity(rows.len());
+        for mut row in rows {
+            let generator = &mut self.row_generator(file_name, key_columns, &row);
+            let row_number = output.len() + 1;
+            // A column can be damaged more than once, and each defect records the true value
+            let original_row = row.clone();
+            let mut record = |column: Option<usize>, kind: DefectKind| {
+                self.defects.push(InjectedDefect {
+                    file_name,
+                    row: row_number,
+                    column,
+                    kind,
+                    original_value: column
+                        .map(|column| original_row[column].clone())
+                        .unwrap_or_default(),
+                })
+            };
+
+            if let Some(column) = choose_filled_column(generator, &row, columns.typos) {
+                if generator.gen_bool(self.rates.typo) {
+                    record(Some(column), DefectKind::Typo);
+                    row[column] = generate_typo(generator, &row[column]);
+                }
+            }
+            if let Some((first_name, last_name)) = columns.names {
+                if generator.gen_bool(self.rates.swapped_names) {
+                    record(None, DefectKind::SwappedNames);
+                    row.swap(first_name, last_name);
+                }
+            }
+            if let Some(column) = columns.pesel.filter(|column| !row[*column].is_empty()) {
+                if generator.gen_bool(self.rates.malformed_pesel) {
+                    record(Some(column), DefectKind::MalformedPesel);
+                    row[column] = generate_malformed_number(generator, &row[column]);
+                }
+            }
+            if let Some(column) = columns
+                .phone_number
+                .filter(|column| !row[*column].is_empty())
+            {
+                if generator.gen_bool(self.rates.malformed_phone_number) {
+                    record(Some(column), DefectKind::MalformedPhoneNumber);
+                    row[column] = generate_malformed_number(generator, &row[column]);
+                }
+            }
+            if let Some(column) = choose_filled_column(generator, &row, columns.dates) {
+                if generator.gen_bool(self.rates.out_of_range_date) {
+                    record(Some(column), DefectKind::OutOfRangeDate);
+                    let year = OUT_OF_RANGE_YEARS.choose(generator).unwrap();
+                    row[column].replace_range(0..4, year);
+                }
+            }
+            if let Some(column) = choose_filled_column(generator, &row, columns.foreign_keys) {
+                if generator.gen_bool(self.rates.orphan_foreign_key) {
+                    record(Some(column), DefectKind::OrphanForeignKey);
+                    let id = row[column].parse::<u64>().unwrap_or_default();
+                    row[column] = (id + ORPHAN_FOREIGN_KEY_OFFSET).to_string();
+                }
+            }
+            if let Some(column) = choose_filled_column(generator, &row, columns.mandatory) {
+                if generator.gen_bool(self.rates.missing_field) {
+                    record(Some(column), DefectKind::MissingField);
+                    row[column].clear();
+                }
+            }
+
+            let is_duplicated = generator.gen_bool(self.rates.duplicated_row);
+            if is_duplicated {
+                output.push(row.clone());
+                self.defects.push(InjectedDefect {
+                    file_name,
+                    row: output.len() + 1,
+                    column: None,
+                    kind: DefectKind::DuplicatedRow,
+                    original_value: "".to_string(),
+                });
+            }
+            output.push(row);
+        }
+
+        output
+    }
+
+    fn row_generator(&self, file_name: &str, key_columns: &[usize], row: &[String]) -> StdRng {
+        let mut hasher = DefaultHasher::new();
+        self.seed.hash(&mut hasher);
+        file_name.hash(&mut hasher);
+        for column in key_columns {
+            row[*column].hash(&mut hasher);
+        }
+        StdRng::seed_from_u64(hasher.finish())
+    }
+}
+
+fn choose_filled_column(
+    generator: &mut StdRng,
+    row: &[String],
+    columns: &[usize],
+) -> Option<usize> {
+    columns
+        .iter()
+        .copied()
+        .filter(|column| !row[*column].is_empty())
+        .collect::<Vec<_>>()
+        .choose(generator)
+        .copied()
+}
+
+/// Swaps two neighbouring characters, drops one or types one twice.
+fn generate_typo(generator: &mut StdRng, value: &str) -> String {
+    let mut chars = value.chars().collect::<Vec<_>>();
+    let position = generator.gen_range(0..chars.len());
+    match generator.gen_range(0..3) {
+        0 if position + 1 < chars.len() && chars[position] != chars[position + 1] => {
+            chars.swap(position, position + 1)
+        }
+        1 if chars.len() > 1 => {
+            chars.remove(position);
+        }
+        _ => chars.insert(position, chars[position]),
+    }
+    chars.into_iter().collect()
+}
+
+/// Drops a digit, adds one or replaces one with a letter.
+fn generate_malformed_number(generator: &mut StdRng, value: &str) -> String {
+    let mut chars = value.chars().collect::<Vec<_>>();
+    let position = generator.gen_range(0..chars.len());
+    match generator.gen_range(0..3) {
+        0 => {
+            chars.remove(position);
+        }
+        1 => chars.insert(
+            position,
+            char::from_digit(generator.gen_range(0..10), 10).unwrap(),
+        ),
+        _ => chars[position] = 'O',
+    }
+    chars.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::random;
+
+    use crate::defect::{DefectColumns, DefectInjector, DefectKind, DefectRates};
+
+    #[test]
+    fn should_record_every_injected_defect_with_its_row() {
+        let mut injector = DefectInjector::new(
+            DefectRates {
+                duplicated_row: 0.5,
+                missing_field: 0.0,
+                typo: 0.5,
+                swapped_names: 0.0,
+                malformed_pesel: 0.0,
+                malformed_phone_number: 0.0,
+                out_of_range_date: 0.0,
+                orphan_foreign_key: 0.0,
+            },
+            random(),
+        );
+        let columns = DefectColumns {
+            mandatory: &[],
+            typos: &[1],
+            names: None,
+            pesel: None,
+            phone_number: None,
+            dates: &[],
+            foreign_keys: &[],
+        };
+        let rows = (0..100)
+            .map(|id| vec![id.to_string(), "KOWALSKI".to_string()])
+            .collect::<Vec<_>>();
+
+        let output = injector.inject("test.csv", &columns, &[0], rows);
+
+        let duplicates = injector
+            .defects
+            .iter()
+            .filter(|defect| defect.kind == DefectKind::DuplicatedRow)
+            .collect::<Vec<_>>();
+        assert_eq!(output.len(), 100 + duplicates.len());
+        for duplicate in duplicates {
+            assert_eq!(output[duplicate.row - 1], output[duplicate.row - 2]);
+        }
+        for typo in injector
+            .defects
+            .iter()
+            .filter(|defect| defect.kind == DefectKind::Typo)
+        {
+            assert_ne!(output[typo.row - 1][1], typo.original_value);
+        }
+    }
+
+    #[test]
+    fn should_record_the_true_value_of_a_column_damaged_twice() {
+        let mut injector = DefectInjector::new(
+            DefectRates {
+                duplicated_row: 0.0,
+                missing_field: 1.0,
+                typo: 1.0,
+                swapped_names: 0.0,
+                malformed_pesel: 0.0,
+                malformed_phone_number: 0.0,
+                out_of_range_date: 0.0,
+                orphan_foreign_key: 0.0,
+            },
+            random(),
+        );
+        let columns = DefectColumns {
+            mandatory: &[1],
+            typos: &[1],
+            names: None,
+            pesel: None,
+            phone_number: None,
+            dates: &[],
+            foreign_keys: &[],
+        };
+        let rows = vec![vec!["0".to_string(), "KOWALSKI".to_string()]];
+
+        let output = injector.inject("test.csv", &columns, &[0], rows);
+
+        assert_eq!(output[0][1], "");
+        let kinds = injector
+            .defects
+            .iter()
+            .map(|defect| defect.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, [DefectKind::Typo, DefectKind::MissingField]);
+        assert!(injector
+            .defects
+            .iter()
+            .all(|defect| defect.original_value == "KOWALSKI"));
+    }
+}
@@ -0,0 +1,148 @@
+use chrono::{DateTime, Duration, Utc};
+use sorted_vec::SortedVec;
+use std::cmp::Ordering;
+
+// Each priority level postpones a request by 30 minutes, so a less urgent request that has been
+// waiting long enough eventually gets ahead of more urgent ones and is never starved
+const SECONDS_PER_PRIORITY_LEVEL: i64 = 1800;
+
+#[derive(Debug, Copy, Clone)]
+pub struct PatrolRequest {
+    pub report_id: usize,
+    pub place_id: usize,
+    pub station_id: usize,
+    pub priority: u32,
+    pub requested_time: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DispatchQueue {
+    requests: SortedVec<PatrolRequest>,
+}
+
+impl PatrolRequest {
+    fn dispatch_deadline(&self) -> DateTime<Utc> {
+        self.requested_time
+            .checked_add_signed(Duration::seconds(
+                self.priority as i64 * SECONDS_PER_PRIORITY_LEVEL,
+            ))
+            .unwrap()
+    }
+}
+
+impl DispatchQueue {
+    pub fn push(&mut self, request: PatrolRequest) {
+        self.requests.push(request);
+    }
+
+    /// Removes the most urgent request for which `serve` finds the units, and returns it with them.
+    pub fn pop_servable<T>(
+        &mut self,
+        mut serve: impl FnMut(&PatrolRequest) -> Option<T>,
+    ) -> Option<(PatrolRequest, T)> {
+        let (index, units) = self
+            .requests
+            .iter()
+            .enumerate()
+            .find_map(|(index, request)| Some((index, serve(request)?)))?;
+        Some((self.requests.remove_index(index), units))
+    }
+}
+
+impl Eq for PatrolRequest {}
+
+impl PartialEq<Self> for PatrolRequest {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl PartialOrd<Self> for PatrolRequest {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PatrolRequest {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.dispatch_deadline()
+            .cmp(&other.dispatch_deadline())
+            .then(self.priority.cmp(&other.priority))
+            .then(self.requested_time.cmp(&other.requested_time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use crate::dispatch::{DispatchQueue, PatrolRequest};
+
+    #[test]
+    fn should_serve_critical_requests_before_earlier_standard_ones() {
+        let time = Utc.with_ymd_and_hms(2023, 6, 5, 12, 0, 0).unwrap();
+        let mut queue = DispatchQueue::default();
+        queue.push(PatrolRequest {
+            report_id: 0,
+            place_id: 0,
+            station_id: 0,
+            priority: 2,
+            requested_time: time,
+        });
+        queue.push(PatrolRequest {
+            report_id: 1,
+            place_id: 0,
+            station_id: 0,
+            priority: 0,
+            requested_time: time + Duration::minutes(10),
+        });
+
+        assert_eq!(queue.pop_servable(|_| Some(())).unwrap().0.report_id, 1);
+        assert_eq!(queue.pop_servable(|_| Some(())).unwrap().0.report_id, 0);
+    }
+
+    #[test]
+    fn should_not_starve_long_waiting_requests() {
+        let time = Utc.with_ymd_and_hms(2023, 6, 5, 12, 0, 0).unwrap();
+        let mut queue = DispatchQueue::default();
+        queue.push(PatrolRequest {
+            report_id: 0,
+            place_id: 0,
+            station_id: 0,
+            priority: 2,
+            requested_time: time,
+        });
+        queue.push(PatrolRequest {
+            report_id: 1,
+            place_id: 0,
+            station_id: 0,
+            priority: 0,
+            requested_time: time + Duration::hours(2),
+        });
+
+        assert_eq!(queue.pop_servable(|_| Some(())).unwrap().0.report_id, 0);
+    }
+
+    #[test]
+    fn should_serve_later_requests_when_the_first_one_cannot_be_served() {
+        let time = Utc.with_ymd_and_hms(2023, 6, 5, 12, 0, 0).unwrap();
+        let mut queue = DispatchQueue::default();
+        for (report_id, priority) in [(0, 0), (1, 1), (2, 2)] {
+            queue.push(PatrolRequest {
+                report_id,
+                place_id: 0,
+                station_id: 0,
+                priority,
+                requested_time: time,
+            });
+        }
+        // Only a single officer is available, and report 0 needs two
+        let serve = |request: &PatrolRequest| (request.report_id != 0).then_some(1);
+
+        let (request, policemen_count) = queue.pop_servable(serve).unwrap();
+        assert_eq!((request.report_id, policemen_count), (1, 1));
+        assert_eq!(queue.pop_servable(serve).unwrap().0.report_id, 2);
+        assert!(queue.pop_servable(serve).is_none());
+        assert_eq!(queue.pop_servable(|_| Some(())).unwrap().0.report_id, 0);
+    }
+}
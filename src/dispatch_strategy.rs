@@ -0,0 +1,663 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use rand::prelude::{IteratorRandom, SliceRandom, ThreadRng};
+
+use crate::patrol::Patrol;
+use crate::person::Policeman;
+use crate::place::Place;
+use crate::report::Report;
+use crate::vehicle::Vehicle;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DispatchStrategyKind {
+    Random,
+    LeastRecentlyUsed,
+    LeastWorkload,
+    StablePartners,
+    NearestUnit,
+}
+
+pub const ALL_DISPATCH_STRATEGY_KINDS: [DispatchStrategyKind; 5] = [
+    DispatchStrategyKind::Random,
+    DispatchStrategyKind::LeastRecentlyUsed,
+    DispatchStrategyKind::LeastWorkload,
+    DispatchStrategyKind::StablePartners,
+    DispatchStrategyKind::NearestUnit,
+];
+
+/// Decides which of the available officers and vehicles are sent to a report.
+pub trait DispatchStrategy {
+    /// Returns the ids of the chosen officers, which may be fewer than `count` if there are not
+    /// enough available officers.
+    fn choose_policemen(
+        &mut self,
+        generator: &mut ThreadRng,
+        available: &[&Policeman],
+        count: usize,
+        report: &Report,
+    ) -> Vec<usize>;
+
+    fn choose_vehicle(
+        &mut self,
+        generator: &mut ThreadRng,
+        available: &[&Vehicle],
+        report: &Report,
+    ) -> Option<usize>;
+
+    fn on_patrol_sent(&mut self, _patrol: &Patrol, _report: &Report) {}
+}
+
+impl DispatchStrategyKind {
+    /// The name of the strategy on the command line.
+    pub fn code(&self) -> &'static str {
+        match self {
+            DispatchStrategyKind::Random => "random",
+            DispatchStrategyKind::LeastRecentlyUsed => "least-recently-used",
+            DispatchStrategyKind::LeastWorkload => "least-workload",
+            DispatchStrategyKind::StablePartners => "stable-partners",
+            DispatchStrategyKind::NearestUnit => "nearest-unit",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        ALL_DISPATCH_STRATEGY_KINDS
+            .into_iter()
+            .find(|kind| kind.code() == code)
+    }
+
+    pub fn create(self, places: &'static [Place]) -> Box<dyn DispatchStrategy> {
+        match self {
+            DispatchStrategyKind::Random => Box::new(RandomStrategy),
+            DispatchStrategyKind::LeastRecentlyUsed => Box::new(LeastRecentlyUsedStrategy {
+                usage: UnitUsage::default(),
+            }),
+            DispatchStrategyKind::LeastWorkload => Box::new(LeastWorkloadStrategy {
+                usage: UnitUsage::default(),
+            }),
+            DispatchStrategyKind::StablePartners => Box::new(StablePartnersStrategy {
+                partners: HashMap::new(),
+            }),
+            DispatchStrategyKind::NearestUnit => Box::new(NearestUnitStrategy { places }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct UnitUsage {
+    policemen_last_sending: HashMap<usize, DateTime<Utc>>,
+    policemen_busy_seconds: HashMap<usize, i64>,
+    vehicles_last_sending: HashMap<usize, DateTime<Utc>>,
+    vehicles_busy_seconds: HashMap<usize, i64>,
+}
+
+impl UnitUsage {
+    fn record(&mut self, patrol: &Patrol) {
+        let busy_seconds = (patrol.finish_time - patrol.sending_time).num_seconds();
+        for policeman_id in &patrol.policemen_ids {
+            self.policemen_last_sending
+                .insert(*policeman_id, patrol.sending_time);
+            *self
+                .policemen_busy_seconds
+                .entry(*policeman_id)
+                .or_default() += busy_seconds;
+        }
+        self.vehicles_last_sending
+            .insert(patrol.vehicle_id, patrol.sending_time);
+        *self
+            .vehicles_busy_seconds
+            .entry(patrol.vehicle_id)
+            .or_default() += busy_seconds;
+    }
+}
+
+/// Chooses `count` ids with the lowest key, breaking ties randomly.
+fn choose_lowest<K: Ord>(
+    generator: &mut ThreadRng,
+    ids: impl Iterator<Item = usize>,
+    count: usize,
+    key: impl Fn(usize) -> K,
+) -> Vec<usize> {
+    let mut ids = ids.collect::<Vec<_>>();
+    ids.shuffle(generator);
+    ids.sort_by_key(|id| key(*id));
+    ids.truncate(count);
+    ids
+}
+
+struct RandomStrategy;
+
+impl DispatchStrategy for RandomStrategy {
+    fn choose_policemen(
+        &mut self,
+        generator: &mut ThreadRng,
+        available: &[&Policeman],
+        count: usize,
+        _report: &Report,
+    ) -> Vec<usize> {
+        available
+            .iter()
+            .map(|policeman| policeman.person.id)
+            .choose_multiple(generator, count)
+    }
+
+    fn choose_vehicle(
+        &mut self,
+        generator: &mut ThreadRng,
+        available: &[&Vehicle],
+        _report: &Report,
+    ) -> Option<usize> {
+        available.iter().map(|vehicle| vehicle.id).choose(generator)
+    }
+}
+
+/// Sends the units which have been waiting the longest since their last patrol.
+struct LeastRecentlyUsedStrategy {
+    usage: UnitUsage,
+}
+
+impl DispatchStrategy for LeastRecentlyUsedStrategy {
+    fn choose_policemen(
+        &mut self,
+        generator: &mut ThreadRng,
+        available: &[&Policeman],
+        count: usize,
+        _report: &Report,
+    ) -> Vec<usize> {
+        let ids = available.iter().map(|policeman| policeman.person.id);
+        choose_lowest(generator, ids, count, |id| {
+            self.usage.policemen_last_sending.get(&id).copied()
+        })
+    }
+
+    fn choose_vehicle(
+        &mut self,
+        generator: &mut ThreadRng,
+        available: &[&Vehicle],
+        _report: &Report,
+    ) -> Option<usize> {
+        let ids = available.iter().map(|vehicle| vehicle.id);
+        choose_lowest(generator, ids, 1, |id| {
+            self.usage.vehicles_last_sending.get(&id).copied()
+        })
+        .pop()
+    }
+
+    fn on_patrol_sent(&mut self, patrol: &Patrol, _report: &Report) {
+        self.usage.record(patrol);
+    }
+}
+
+/// Sends the units with the lowest total time spent on patrols.
+struct LeastWorkloadStrategy {
+    usage: UnitUsage,
+}
+
+impl DispatchStrategy for LeastWorkloadStrategy {
+    fn choose_policemen(
+        &mut self,
+        generator: &mut ThreadRng,
+        available: &[&Policeman],
+        count: usize,
+        _report: &Report,
+    ) -> Vec<usize> {
+        let ids = available.iter().map(|policeman| policeman.person.id);
+        choose_lowest(generator, ids, count, |id| {
+            self.usage
+                .policemen_busy_seconds
+                .get(&id)
+                .copied()
+                .unwrap_or_default()
+        })
+    }
+
+    fn choose_vehicle(
+        &mut self,
+        generator: &mut ThreadRng,
+        available: &[&Vehicle],
+        _report: &Report,
+    ) -> Option<usize> {
+        let ids = available.iter().map(|vehicle| vehicle.id);
+        choose_lowest(generator, ids, 1, |id| {
+            self.usage
+                .vehicles_busy_seconds
+                .get(&id)
+                .copied()
+                .unwrap_or_default()
+        })
+        .pop()
+    }
+
+    fn on_patrol_sent(&mut self, patrol: &Patrol, _report: &Report) {
+        self.usage.record(patrol);
+    }
+}
+
+/// Officers are paired on their first patrol together and from then on are preferably sent with
+/// their partner. A new partner is found only when the previous one is busy or has resigned.
+struct StablePartnersStrategy {
+    partners: HashMap<usize, usize>,
+}
+
+impl DispatchStrategy for StablePartnersStrategy {
+    fn choose_policemen(
+        &mut self,
+        generator: &mut ThreadRng,
+        available: &[&Policeman],
+        count: usize,
+        _report: &Report,
+    ) -> Vec<usize> {
+        let available_ids = available
+            .iter()
+            .map(|policeman| policeman.person.id)
+            .collect::<Vec<_>>();
+        let mut chosen = vec![];
+
+        while chosen.len() + 2 <= count {
+            let pair = available_ids
+                .iter()
+                .filter(|id| !chosen.contains(*id))
+                .filter_map(|id| self.partners.get(id).map(|partner| (*id, *partner)))
+                .filter(|(_, partner)| available_ids.contains(partner))
+                .filter(|(_, partner)| !chosen.contains(partner))
+                .choose(generator);
+            let Some((first, second)) = pair else {
+                break;
+            };
+            chosen.push(first);
+            chosen.push(second);
+        }
+
+        let remaining = available_ids
+            .iter()
+            .copied()
+            .filter(|id| !chosen.contains(id))
+            .choose_multiple(generator, count.saturating_sub(chosen.len()));
+        chosen.extend(remaining);
+
+        chosen
+    }
+
+    fn choose_vehicle(
+        &mut self,
+        generator: &mut ThreadRng,
+        available: &[&Vehicle],
+        _report: &Report,
+    ) -> Option<usize> {
+        available.iter().map(|vehicle| vehicle.id).choose(generator)
+    }
+
+    /// Officers are paired only once the patrol is actually sent, as the chosen ones may still be
+    /// dropped if there is no vehicle for them.
+    fn on_patrol_sent(&mut self, patrol: &Patrol, _report: &Report) {
+        for pair in patrol.policemen_ids.chunks(2) {
+            if let [first, second] = pair {
+                self.pair(*first, *second);
+            }
+        }
+    }
+}
+
+impl StablePartnersStrategy {
+    fn pair(&mut self, first: usize, second: usize) {
+        for id in [first, second] {
+            if let Some(previous_partner) = self.partners.remove(&id) {
+                self.partners.remove(&previous_partner);
+            }
+        }
+        self.partners.insert(first, second);
+        self.partners.insert(second, first);
+    }
+}
+
+/// Sends the available vehicle closest to the reported place.
+struct NearestUnitStrategy {
+    places: &'static [Place],
+}
+
+impl DispatchStrategy for NearestUnitStrategy {
+    fn choose_policemen(
+        &mut self,
+        generator: &mut ThreadRng,
+        available: &[&Policeman],
+        count: usize,
+        _report: &Report,
+    ) -> Vec<usize> {
+        available
+            .iter()
+            .map(|policeman| policeman.person.id)
+            .choose_multiple(generator, count)
+    }
+
+    fn choose_vehicle(
+        &mut self,
+        _generator: &mut ThreadRng,
+        available: &[&Vehicle],
+        report: &Report,
+    ) -> Option<usize> {
+        let target = self.places.get(report.place_id).unwrap().coordinates;
+        available
+            .iter()
+            .min_by(|first, second| {
+                let first_distance = first.position.distance_km(&target);
+                let second_distance = second.position.distance_km(&target);
+                first_distance.total_cmp(&second_distance)
+            })
+            .map(|vehicle| vehicle.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{Duration, TimeZone, Utc};
+    use rand::thread_rng;
+
+    use crate::audit::Audit;
+    use crate::dispatch_strategy::{
+        DispatchStrategy, DispatchStrategyKind, StablePartnersStrategy, ALL_DISPATCH_STRATEGY_KINDS,
+    };
+    use crate::patrol::Patrol;
+    use crate::person::{Person, Policeman};
+    use crate::place::{get_all_places, Coordinates};
+    use crate::report::Report;
+    use crate::station::get_all_stations;
+    use crate::vehicle::Vehicle;
+
+    // Each choice is repeated, as ties are broken randomly
+    const REPETITIONS: usize = 20;
+
+    #[test]
+    fn should_parse_every_strategy_code() {
+        for kind in ALL_DISPATCH_STRATEGY_KINDS {
+            assert_eq!(DispatchStrategyKind::from_code(kind.code()), Some(kind));
+        }
+        assert_eq!(DispatchStrategyKind::from_code("fastest"), None);
+    }
+
+    #[test]
+    fn should_choose_distinct_random_units() {
+        let mut generator = thread_rng();
+        let time = Utc.with_ymd_and_hms(2015, 6, 1, 0, 0, 0).unwrap();
+        let policemen = (0..4)
+            .map(|id| {
+                Policeman::generate_just_employed_with_id_and_station(&mut generator, &time, id, 0)
+            })
+            .collect::<Vec<_>>();
+        let vehicles = (0..2)
+            .map(|id| {
+                Vehicle::generate_new_with_id_and_station(
+                    &mut generator,
+                    id,
+                    get_all_stations().first().unwrap(),
+                    time,
+                )
+            })
+            .collect::<Vec<_>>();
+        let reporter = Person::generate_with_id(&mut generator, 0);
+        let report = Report::generate_with_time_reporter_and_id(
+            &mut generator,
+            time,
+            get_all_places().len(),
+            &reporter,
+            0,
+        );
+        let mut strategy = DispatchStrategyKind::Random.create(get_all_places());
+
+        for _ in 0..REPETITIONS {
+            let mut ids = strategy.choose_policemen(
+                &mut generator,
+                &policemen.iter().collect::<Vec<_>>(),
+                2,
+                &report,
+            );
+            ids.sort();
+            assert_eq!(ids.len(), 2);
+            assert_ne!(ids[0], ids[1]);
+            let vehicle_id = strategy.choose_vehicle(
+                &mut generator,
+                &vehicles.iter().collect::<Vec<_>>(),
+                &report,
+            );
+            assert!(vehicle_id.is_some());
+        }
+    }
+
+    #[test]
+    fn should_send_never_sent_units_first() {
+        let mut generator = thread_rng();
+        let time = Utc.with_ymd_and_hms(2015, 6, 1, 0, 0, 0).unwrap();
+        let policemen = (0..5)
+            .map(|id| {
+                Policeman::generate_just_employed_with_id_and_station(&mut generator, &time, id, 0)
+            })
+            .collect::<Vec<_>>();
+        let vehicles = (0..3)
+            .map(|id| {
+                Vehicle::generate_new_with_id_and_station(
+                    &mut generator,
+                    id,
+                    get_all_stations().first().unwrap(),
+                    time,
+                )
+            })
+            .collect::<Vec<_>>();
+        let reporter = Person::generate_with_id(&mut generator, 0);
+        let report = Report::generate_with_time_reporter_and_id(
+            &mut generator,
+            time,
+            get_all_places().len(),
+            &reporter,
+            0,
+        );
+        let mut strategy = DispatchStrategyKind::LeastRecentlyUsed.create(get_all_places());
+        for (policemen_ids, vehicle_id, sending_time) in [
+            (vec![0, 1], 0, time),
+            (vec![2, 3], 1, time + Duration::hours(1)),
+        ] {
+            let patrol = Patrol {
+                id: 0,
+                report_id: 0,
+                policemen_ids,
+                vehicle_id,
+                station_id: 0,
+                distance_km: 0.0,
+                fuel_used_l: 0.0,
+                odometer_km: 0.0,
+                queued_time: sending_time,
+                sending_time,
+                arrival_time: sending_time,
+                finish_time: sending_time + Duration::minutes(30),
+                audit: Audit {
+                    created_at: sending_time,
+                    updated_at: sending_time,
+                    created_by: 1,
+                },
+            };
+            strategy.on_patrol_sent(&patrol, &report);
+        }
+
+        for _ in 0..REPETITIONS {
+            let mut ids = strategy.choose_policemen(
+                &mut generator,
+                &policemen.iter().collect::<Vec<_>>(),
+                3,
+                &report,
+            );
+            ids.sort();
+            assert_eq!(ids, vec![0, 1, 4]);
+            let vehicle_id = strategy.choose_vehicle(
+                &mut generator,
+                &vehicles.iter().collect::<Vec<_>>(),
+                &report,
+            );
+            assert_eq!(vehicle_id, Some(2));
+        }
+    }
+
+    #[test]
+    fn should_send_units_with_lowest_workload() {
+        let mut generator = thread_rng();
+        let time = Utc.with_ymd_and_hms(2015, 6, 1, 0, 0, 0).unwrap();
+        let policemen = (0..4)
+            .map(|id| {
+                Policeman::generate_just_employed_with_id_and_station(&mut generator, &time, id, 0)
+            })
+            .collect::<Vec<_>>();
+        let vehicles = (0..2)
+            .map(|id| {
+                Vehicle::generate_new_with_id_and_station(
+                    &mut generator,
+                    id,
+                    get_all_stations().first().unwrap(),
+                    time,
+                )
+            })
+            .collect::<Vec<_>>();
+        let reporter = Person::generate_with_id(&mut generator, 0);
+        let report = Report::generate_with_time_reporter_and_id(
+            &mut generator,
+            time,
+            get_all_places().len(),
+            &reporter,
+            0,
+        );
+        let mut strategy = DispatchStrategyKind::LeastWorkload.create(get_all_places());
+        for (policemen_ids, vehicle_id, sending_time, minutes) in [
+            (vec![0, 1], 0, time, 30),
+            (vec![2, 3], 1, time + Duration::hours(1), 20),
+            (vec![0, 3], 1, time + Duration::hours(2), 20),
+        ] {
+            let patrol = Patrol {
+                id: 0,
+                report_id: 0,
+                policemen_ids,
+                vehicle_id,
+                station_id: 0,
+                distance_km: 0.0,
+                fuel_used_l: 0.0,
+                odometer_km: 0.0,
+                queued_time: sending_time,
+                sending_time,
+                arrival_time: sending_time,
+                finish_time: sending_time + Duration::minutes(minutes),
+                audit: Audit {
+                    created_at: sending_time,
+                    updated_at: sending_time,
+                    created_by: 1,
+                },
+            };
+            strategy.on_patrol_sent(&patrol, &report);
+        }
+
+        for _ in 0..REPETITIONS {
+            let mut ids = strategy.choose_policemen(
+                &mut generator,
+                &policemen.iter().collect::<Vec<_>>(),
+                2,
+                &report,
+            );
+            ids.sort();
+            assert_eq!(ids, vec![1, 2]);
+            let vehicle_id = strategy.choose_vehicle(
+                &mut generator,
+                &vehicles.iter().collect::<Vec<_>>(),
+                &report,
+            );
+            assert_eq!(vehicle_id, Some(0));
+        }
+    }
+
+    #[test]
+    fn should_send_existing_pair_again() {
+        let mut generator = thread_rng();
+        let time = Utc.with_ymd_and_hms(2015, 6, 1, 0, 0, 0).unwrap();
+        let policemen = (0..4)
+            .map(|id| {
+                Policeman::generate_just_employed_with_id_and_station(&mut generator, &time, id, 0)
+            })
+            .collect::<Vec<_>>();
+        let available_policemen = policemen.iter().collect::<Vec<_>>();
+        let reporter = Person::generate_with_id(&mut generator, 0);
+        let report = Report::generate_with_time_reporter_and_id(
+            &mut generator,
+            time,
+            get_all_places().len(),
+            &reporter,
+            0,
+        );
+        let mut strategy = StablePartnersStrategy {
+            partners: HashMap::new(),
+        };
+
+        strategy.choose_policemen(&mut generator, &available_policemen, 2, &report);
+        assert!(strategy.partners.is_empty());
+
+        let patrol = Patrol {
+            id: 0,
+            report_id: 0,
+            policemen_ids: vec![1, 3],
+            vehicle_id: 0,
+            station_id: 0,
+            distance_km: 0.0,
+            fuel_used_l: 0.0,
+            odometer_km: 0.0,
+            queued_time: time,
+            sending_time: time,
+            arrival_time: time,
+            finish_time: time + Duration::minutes(30),
+            audit: Audit {
+                created_at: time,
+                updated_at: time,
+                created_by: 1,
+            },
+        };
+        strategy.on_patrol_sent(&patrol, &report);
+        for _ in 0..REPETITIONS {
+            let mut ids =
+                strategy.choose_policemen(&mut generator, &available_policemen, 2, &report);
+            ids.sort();
+            assert_eq!(ids, vec![1, 3]);
+        }
+    }
+
+    #[test]
+    fn should_send_nearest_vehicle() {
+        let mut generator = thread_rng();
+        let time = Utc.with_ymd_and_hms(2015, 6, 1, 0, 0, 0).unwrap();
+        let reporter = Person::generate_with_id(&mut generator, 0);
+        let report = Report::generate_with_time_reporter_and_id(
+            &mut generator,
+            time,
+            get_all_places().len(),
+            &reporter,
+            0,
+        );
+        let target = get_all_places().get(report.place_id).unwrap().coordinates;
+        let vehicles = [1.0, 0.01, 0.1]
+            .iter()
+            .enumerate()
+            .map(|(id, latitude_offset)| {
+                let mut vehicle = Vehicle::generate_new_with_id_and_station(
+                    &mut generator,
+                    id,
+                    get_all_stations().first().unwrap(),
+                    time,
+                );
+                vehicle.position = Coordinates {
+                    latitude: target.latitude + latitude_offset,
+                    longitude: target.longitude,
+                };
+                vehicle
+            })
+            .collect::<Vec<_>>();
+        let available_vehicles = vehicles.iter().collect::<Vec<_>>();
+        let mut strategy = DispatchStrategyKind::NearestUnit.create(get_all_places());
+
+        for _ in 0..REPETITIONS {
+            let vehicle_id = strategy.choose_vehicle(&mut generator, &available_vehicles, &report);
+            assert_eq!(vehicle_id, Some(1));
+        }
+    }
+}
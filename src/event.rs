@@ -1,14 +1,26 @@
 use crate::person::Policeman;
-use chrono::{DateTime, Utc};
+use crate::vehicle::{Vehicle, DAYS_BETWEEN_SERVICES};
+use chrono::{DateTime, Duration, Utc};
+use rand::prelude::ThreadRng;
 use std::cmp::Ordering;
 
 #[derive(Debug, Copy, Clone)]
 pub enum EventAction {
-    PolicemanEmployment,
-    PolicemanResignation,
+    PolicemanEmployment(usize),
+    PolicemanResignation(usize),
     Report,
+    ReportCorrection(usize),
+    ReportDeletion(usize),
     SendPatrol(usize),
+    Dispatch,
+    PatrolArrival(usize),
     FinishedPatrol(usize),
+    ShiftChange,
+    VehicleService(usize),
+    VehicleBreakdown(usize),
+    VehicleRepaired(usize),
+    VehicleDecommission(usize),
+    VehicleProcurement(usize),
     Snapshot(&'static str, bool),
     PolicemanLastNameChange,
 }
@@ -23,9 +35,30 @@ impl Event {
     pub fn from_policeman_resignation_event(policeman: &Policeman) -> Event {
         Event {
             time: policeman.resignment_date,
-            action: EventAction::PolicemanResignation,
+            action: EventAction::PolicemanResignation(policeman.person.id),
         }
     }
+
+    /// The first service, the first breakdown and the retirement of a just commissioned vehicle.
+    pub fn from_vehicle_commissioning(generator: &mut ThreadRng, vehicle: &Vehicle) -> [Event; 3] {
+        [
+            Event {
+                time: vehicle
+                    .commissioning_date
+                    .checked_add_signed(Duration::days(DAYS_BETWEEN_SERVICES))
+                    .unwrap(),
+                action: EventAction::VehicleService(vehicle.id),
+            },
+            Event {
+                time: vehicle.generate_next_breakdown_time(generator, vehicle.commissioning_date),
+                action: EventAction::VehicleBreakdown(vehicle.id),
+            },
+            Event {
+                time: vehicle.generate_retirement_date(generator),
+                action: EventAction::VehicleDecommission(vehicle.id),
+            },
+        ]
+    }
 }
 
 impl Eq for Event {}
@@ -1,26 +1,367 @@
-use std::fs::File;
+use std::fs::{read, read_to_string, File};
 use std::io::Write;
 use std::ops::Add;
 use std::path::Path;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, SecondsFormat, Utc};
+use rust_xlsxwriter::{Color, ExcelDateTime, Format, FormatBorder, Workbook};
 
+use crate::citizen::CitizenRegistry;
+use crate::defect::{DefectColumns, DefectInjector, DefectRates};
+use crate::outcome::{InvolvedPerson, PatrolOutcome};
 use crate::patrol::Patrol;
 use crate::person::Policeman;
 use crate::place::Place;
 use crate::report::Report;
-use crate::vehicle::Vehicle;
+use crate::roster::{Absence, RosterEntry};
+use crate::source_format::{FormattedColumns, Source, SourceFormats, ISO_DATE_FORMAT};
+use crate::station::{get_all_stations, Station};
+use crate::statistics::{Statistics, PERCENTILES};
+use crate::vehicle::{Maintenance, Vehicle, VehicleState};
+use crate::warehouse::Warehouse;
 
 const PLACES_OUTPUT_DIRECTORY: &str = "output/";
-const PLACES_OUTPUT_FILE: &str = "places.csv";
-const REPORTS_OUTPUT_FILE: &str = "reports.csv";
-const POLICEMEN_DB_OUTPUT_FILE: &str = "policemen_db.csv";
-const POLICEMEN_CSV_OUTPUT_FILE: &str = "policemen_csv.csv";
-const VEHICLE_DB_OUTPUT_FILE: &str = "vehicle_db.csv";
+pub const PLACES_OUTPUT_FILE: &str = "places.csv";
+const STATIONS_OUTPUT_FILE: &str = "stations.csv";
+pub const REPORTS_OUTPUT_FILE: &str = "reports.csv";
+const PERSONS_OUTPUT_FILE: &str = "persons.csv";
+const OUTCOMES_OUTPUT_FILE: &str = "outcomes.csv";
+const INVOLVED_PERSONS_OUTPUT_FILE: &str = "involved_persons.csv";
+pub const POLICEMEN_DB_OUTPUT_FILE: &str = "policemen_db.csv";
+pub const POLICEMEN_CSV_OUTPUT_FILE: &str = "policemen_csv.csv";
+const POLICEMEN_HR_OUTPUT_FILE: &str = "hr_export.xml";
+const ROSTER_OUTPUT_FILE: &str = "roster.csv";
+const ABSENCES_OUTPUT_FILE: &str = "absences.csv";
+pub const VEHICLE_DB_OUTPUT_FILE: &str = "vehicle_db.csv";
 const VEHICLE_CSV_OUTPUT_FILE: &str = "vehicle_csv.csv";
-const PATROLS_OUTPUT_FILE: &str = "patrols.csv";
-const POLICEMEN_PATROLS_OUTPUT_FILE: &str = "policemen_patrols.csv";
+const VEHICLE_FLEET_OUTPUT_FILE: &str = "fleet_feed.json";
+const MAINTENANCES_OUTPUT_FILE: &str = "maintenances.csv";
+pub const PATROLS_OUTPUT_FILE: &str = "patrols.csv";
+pub const POLICEMEN_PATROLS_OUTPUT_FILE: &str = "policemen_patrols.csv";
+const OFFICER_DIMENSION_OUTPUT_FILE: &str = "dw_officer_dim.csv";
+const VEHICLE_DIMENSION_OUTPUT_FILE: &str = "dw_vehicle_dim.csv";
+const STATION_DIMENSION_OUTPUT_FILE: &str = "dw_station_dim.csv";
+const PLACE_DIMENSION_OUTPUT_FILE: &str = "dw_place_dim.csv";
+const OUTCOME_DIMENSION_OUTPUT_FILE: &str = "dw_outcome_dim.csv";
+const REPORTER_DIMENSION_OUTPUT_FILE: &str = "dw_reporter_dim.csv";
+const REPORT_TYPE_DIMENSION_OUTPUT_FILE: &str = "dw_report_type_dim.csv";
+const DATE_DIMENSION_OUTPUT_FILE: &str = "dw_date_dim.csv";
+const TIME_DIMENSION_OUTPUT_FILE: &str = "dw_time_dim.csv";
+const PATROL_FACT_OUTPUT_FILE: &str = "dw_patrol_fact.csv";
+const PATROL_OFFICER_BRIDGE_OUTPUT_FILE: &str = "dw_patrol_officer_bridge.csv";
+const DEFECTS_MANIFEST_OUTPUT_FILE: &str = "defects_manifest.csv";
+const SPREADSHEETS_OUTPUT_FILE: &str = "spreadsheets.xlsx";
+const STATISTICS_JSON_OUTPUT_FILE: &str = "statistics.json";
+const STATISTICS_TEXT_OUTPUT_FILE: &str = "statistics.txt";
+const SPREADSHEET_DATE_FORMAT: &str = "dd.mm.yyyy";
 const COLUMN_DELIMITER: &str = ",";
+// The HR system writes dates the Polish way
+const HR_DATE_FORMAT: &str = "%d.%m.%Y";
+
+// The source files which get damaged by the defect injector, and their damageable columns. Orphan
+// foreign keys are never injected into the SOURCE_KEYS columns, by which the diff matches the rows,
+// so the bridge files only get duplicated rows
+const DEFECT_FILES: [(&str, DefectColumns); 8] = [
+    (
+        PLACES_OUTPUT_FILE,
+        DefectColumns {
+            mandatory: &[1, 2],
+            typos: &[1, 2],
+            names: None,
+            pesel: None,
+            phone_number: None,
+            dates: &[],
+            foreign_keys: &[],
+        },
+    ),
+    (
+        PERSONS_OUTPUT_FILE,
+        DefectColumns {
+            mandatory: &[1, 2],
+            typos: &[1, 2],
+            names: Some((1, 2)),
+            pesel: Some(5),
+            phone_number: Some(4),
+            dates: &[3],
+            foreign_keys: &[],
+        },
+    ),
+    (
+        REPORTS_OUTPUT_FILE,
+        DefectColumns {
+            mandatory: &[1, 2, 3],
+            typos: &[5, 6],
+            names: Some((5, 6)),
+            pesel: None,
+            phone_number: Some(4),
+            dates: &[2],
+            foreign_keys: &[1, 8],
+        },
+    ),
+    (
+        POLICEMEN_CSV_OUTPUT_FILE,
+        DefectColumns {
+            mandatory: &[1, 4, 5, 6],
+            typos: &[4, 5],
+            names: Some((4, 5)),
+            pesel: Some(6),
+            phone_number: None,
+            dates: &[2, 3],
+            foreign_keys: &[],
+        },
+    ),
+    (
+        VEHICLE_CSV_OUTPUT_FILE,
+        DefectColumns {
+            mandatory: &[1, 2],
+            typos: &[2],
+            names: None,
+            pesel: None,
+            phone_number: None,
+            dates: &[5],
+            foreign_keys: &[],
+        },
+    ),
+    (
+        PATROLS_OUTPUT_FILE,
+        DefectColumns {
+            mandatory: &[1, 2, 3],
+            typos: &[],
+            names: None,
+            pesel: None,
+            phone_number: None,
+            dates: &[3, 4, 5],
+            foreign_keys: &[1, 2],
+        },
+    ),
+    (
+        POLICEMEN_PATROLS_OUTPUT_FILE,
+        DefectColumns {
+            mandatory: &[],
+            typos: &[],
+            names: None,
+            pesel: None,
+            phone_number: None,
+            dates: &[],
+            foreign_keys: &[],
+        },
+    ),
+    (
+        INVOLVED_PERSONS_OUTPUT_FILE,
+        DefectColumns {
+            mandatory: &[],
+            typos: &[],
+            names: None,
+            pesel: None,
+            phone_number: None,
+            dates: &[],
+            foreign_keys: &[],
+        },
+    ),
+];
+
+// The source files with the system that produces them, and their columns depending on the format
+const SOURCE_FILES: [(&str, Source, FormattedColumns); 15] = [
+    (
+        PLACES_OUTPUT_FILE,
+        Source::CallCenter,
+        FormattedColumns {
+            dates: &[],
+            datetimes: &[],
+            decimals: &[3, 4],
+        },
+    ),
+    (
+        STATIONS_OUTPUT_FILE,
+        Source::Database,
+        FormattedColumns {
+            dates: &[],
+            datetimes: &[],
+            decimals: &[3, 4],
+        },
+    ),
+    (
+        PERSONS_OUTPUT_FILE,
+        Source::CallCenter,
+        FormattedColumns {
+            dates: &[3],
+            datetimes: &[],
+            decimals: &[],
+        },
+    ),
+    (
+        REPORTS_OUTPUT_FILE,
+        Source::CallCenter,
+        FormattedColumns {
+            dates: &[],
+            datetimes: &[2, 10, 11],
+            decimals: &[],
+        },
+    ),
+    (
+        POLICEMEN_DB_OUTPUT_FILE,
+        Source::Database,
+        FormattedColumns {
+            dates: &[],
+            datetimes: &[3, 4],
+            decimals: &[],
+        },
+    ),
+    (
+        POLICEMEN_CSV_OUTPUT_FILE,
+        Source::PolicemenSpreadsheet,
+        FormattedColumns {
+            dates: &[2, 3, 7],
+            datetimes: &[],
+            decimals: &[],
+        },
+    ),
+    (
+        ROSTER_OUTPUT_FILE,
+        Source::Database,
+        FormattedColumns {
+            dates: &[1],
+            datetimes: &[],
+            decimals: &[],
+        },
+    ),
+    (
+        ABSENCES_OUTPUT_FILE,
+        Source::Database,
+        FormattedColumns {
+            dates: &[2, 3],
+            datetimes: &[],
+            decimals: &[],
+        },
+    ),
+    (
+        VEHICLE_CSV_OUTPUT_FILE,
+        Source::VehicleSpreadsheet,
+        FormattedColumns {
+            dates: &[5, 6],
+            datetimes: &[],
+            decimals: &[],
+        },
+    ),
+    (
+        VEHICLE_DB_OUTPUT_FILE,
+        Source::Database,
+        FormattedColumns {
+            dates: &[],
+            datetimes: &[4, 5, 6, 7],
+            decimals: &[],
+        },
+    ),
+    (
+        MAINTENANCES_OUTPUT_FILE,
+        Source::Database,
+        FormattedColumns {
+            dates: &[],
+            datetimes: &[3, 4],
+            decimals: &[],
+        },
+    ),
+    (
+        PATROLS_OUTPUT_FILE,
+        Source::Dispatch,
+        FormattedColumns {
+            dates: &[],
+            datetimes: &[3, 4, 5, 11, 12],
+            decimals: &[8, 9, 10],
+        },
+    ),
+    (
+        POLICEMEN_PATROLS_OUTPUT_FILE,
+        Source::Dispatch,
+        FormattedColumns {
+            dates: &[],
+            datetimes: &[],
+            decimals: &[],
+        },
+    ),
+    (
+        OUTCOMES_OUTPUT_FILE,
+        Source::Dispatch,
+        FormattedColumns {
+            dates: &[],
+            datetimes: &[],
+            decimals: &[],
+        },
+    ),
+    (
+        INVOLVED_PERSONS_OUTPUT_FILE,
+        Source::Dispatch,
+        FormattedColumns {
+            dates: &[],
+            datetimes: &[],
+            decimals: &[],
+        },
+    ),
+];
+
+/// A source file kept as a sheet of the spreadsheets workbook.
+struct Sheet {
+    file_suffix: &'static str,
+    name: &'static str,
+    headers: &'static [&'static str],
+    numbers: &'static [usize],
+    dates: &'static [usize],
+}
+
+const SHEETS: [Sheet; 2] = [
+    Sheet {
+        file_suffix: POLICEMEN_CSV_OUTPUT_FILE,
+        name: "Policjanci",
+        headers: &[
+            "ID",
+            "Nr sluzbowy",
+            "Data urodzenia",
+            "Data zatrudnienia",
+            "Imie",
+            "Nazwisko",
+            "PESEL",
+            "Data zwolnienia",
+        ],
+        numbers: &[0, 1],
+        dates: &[2, 3, 7],
+    },
+    Sheet {
+        file_suffix: VEHICLE_CSV_OUTPUT_FILE,
+        name: "Pojazdy",
+        headers: &[
+            "ID",
+            "Nr rejestracyjny",
+            "Model",
+            "Rok produkcji",
+            "Liczba miejsc",
+            "Data wprowadzenia",
+            "Data wycofania",
+            "Przebieg km",
+            "Paliwo",
+        ],
+        numbers: &[0, 3, 4, 7],
+        dates: &[5, 6],
+    },
+];
+
+// The source files compared by the diff command, with the columns identifying their rows
+pub const SOURCE_KEYS: [(&str, &[usize]); 15] = [
+    (PLACES_OUTPUT_FILE, &[0]),
+    (STATIONS_OUTPUT_FILE, &[0]),
+    (PERSONS_OUTPUT_FILE, &[0]),
+    (REPORTS_OUTPUT_FILE, &[0]),
+    (POLICEMEN_DB_OUTPUT_FILE, &[0]),
+    (POLICEMEN_CSV_OUTPUT_FILE, &[0]),
+    (ROSTER_OUTPUT_FILE, &[0, 1]),
+    (ABSENCES_OUTPUT_FILE, &[0, 1, 2]),
+    (VEHICLE_CSV_OUTPUT_FILE, &[0]),
+    (VEHICLE_DB_OUTPUT_FILE, &[0]),
+    (MAINTENANCES_OUTPUT_FILE, &[0]),
+    (PATROLS_OUTPUT_FILE, &[0]),
+    (POLICEMEN_PATROLS_OUTPUT_FILE, &[0, 1]),
+    (OUTCOMES_OUTPUT_FILE, &[0]),
+    (INVOLVED_PERSONS_OUTPUT_FILE, &[0, 1]),
+];
 
 macro_rules! datetime_if_happened {
     ($event_date: expr, $snapshot_date: expr) => {
@@ -50,28 +391,77 @@ pub fn write_places_to_file(snapshot_name: &str, places: &[Place]) {
             place.id.to_string(),
             place.city.to_string(),
             place.street.to_string(),
+            format!("{:.6}", place.coordinates.latitude),
+            format!("{:.6}", place.coordinates.longitude),
         ];
         write_to_file(&mut file, items);
     });
 }
 
-pub fn write_reports_to_file(snapshot_name: &str, reports: &[Report]) {
-    let mut file = create_file(snapshot_name, REPORTS_OUTPUT_FILE);
+pub fn write_stations_to_file(snapshot_name: &str, stations: &[Station]) {
+    let mut file = create_file(snapshot_name, STATIONS_OUTPUT_FILE);
 
-    reports.iter().for_each(|report| {
+    stations.iter().for_each(|station| {
         let items = &[
-            report.id.to_string(),
-            report.place_id.to_string(),
-            report.time.to_string(),
-            report.report_type.to_string(),
-            report.reporter.phone_number.to_string(),
-            report.reporter.first_name.to_string(),
-            report.reporter.last_name.to_string(),
+            station.id.to_string(),
+            station.name.to_string(),
+            station.city.to_string(),
+            format!("{:.6}", station.coordinates.latitude),
+            format!("{:.6}", station.coordinates.longitude),
         ];
         write_to_file(&mut file, items);
     });
 }
 
+pub fn write_persons_to_file(snapshot_name: &str, citizens: &CitizenRegistry) {
+    let mut file = create_file(snapshot_name, PERSONS_OUTPUT_FILE);
+
+    citizens.citizens().iter().for_each(|person| {
+        let items = &[
+            person.id.to_string(),
+            person.first_name.to_string(),
+            person.last_name.to_string(),
+            person.birth_date.date_naive().to_string(),
+            person.phone_number.to_string(),
+            person.pesel_number.to_string(),
+        ];
+        write_to_file(&mut file, items);
+    });
+}
+
+pub fn write_reports_to_file(
+    snapshot_name: &str,
+    reports: &[Report],
+    citizens: &CitizenRegistry,
+    snapshot_date: DateTime<Utc>,
+) {
+    let mut file = create_file(snapshot_name, REPORTS_OUTPUT_FILE);
+
+    reports
+        .iter()
+        .filter(|report| report.audit.created_at < snapshot_date)
+        .filter(|report| !report.is_hard_deleted)
+        .for_each(|report| {
+            let reporter = citizens.get(report.reporter_id);
+            let items = &[
+                report.id.to_string(),
+                report.recorded_place_id().to_string(),
+                report.time.to_string(),
+                report.recorded_report_type().to_string(),
+                reporter.phone_number.to_string(),
+                reporter.first_name.to_string(),
+                report.recorded_reporter_last_name(reporter).to_string(),
+                report.station_id.to_string(),
+                report.reporter_id.to_string(),
+                report.is_deleted.to_string(),
+                report.audit.created_at.to_string(),
+                report.audit.updated_at.to_string(),
+                report.audit.created_by.to_string(),
+            ];
+            write_to_file(&mut file, items);
+        });
+}
+
 pub fn write_database_policemen_to_file(snapshot_name: &str, policemen: &[Policeman]) {
     let mut file = create_file(snapshot_name, POLICEMEN_DB_OUTPUT_FILE);
 
@@ -79,6 +469,10 @@ pub fn write_database_policemen_to_file(snapshot_name: &str, policemen: &[Police
         let items = &[
             policeman.person.id.to_string(),
             policeman.service_number.to_string(),
+            policeman.station_id.to_string(),
+            policeman.audit.created_at.to_string(),
+            policeman.audit.updated_at.to_string(),
+            policeman.audit.created_by.to_string(),
         ];
         write_to_file(&mut file, items);
     });
@@ -106,6 +500,87 @@ pub fn write_csv_policemen_to_file(
     });
 }
 
+/// The HR system export, with its own field names and dates, and no database ids.
+pub fn write_hr_policemen_to_file(
+    snapshot_name: &str,
+    policemen: &[Policeman],
+    snapshot_date: DateTime<Utc>,
+) {
+    let mut file = create_file(snapshot_name, POLICEMEN_HR_OUTPUT_FILE);
+    let stations = get_all_stations();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<kadry>\n");
+    policemen.iter().for_each(|policeman| {
+        let elements = [
+            ("numerSluzbowy", policeman.service_number.to_string()),
+            ("imie", policeman.person.first_name.to_string()),
+            ("nazwisko", policeman.person.last_name.to_string()),
+            ("pesel", policeman.person.pesel_number.to_string()),
+            (
+                "dataUrodzenia",
+                policeman
+                    .person
+                    .birth_date
+                    .format(HR_DATE_FORMAT)
+                    .to_string(),
+            ),
+            ("stopien", policeman.rank.to_string()),
+            ("jednostka", stations[policeman.station_id].name.to_string()),
+            (
+                "dataZatrudnienia",
+                policeman.employment_date.format(HR_DATE_FORMAT).to_string(),
+            ),
+        ];
+        xml.push_str("  <pracownik>\n");
+        for (tag, value) in elements {
+            xml.push_str(&format!("    <{tag}>{}</{tag}>\n", escape_xml(&value)));
+        }
+        if policeman.resignment_date < snapshot_date {
+            let date = policeman.resignment_date.format(HR_DATE_FORMAT);
+            xml.push_str(&format!("    <dataZwolnienia>{date}</dataZwolnienia>\n"));
+        }
+        xml.push_str("  </pracownik>\n");
+    });
+    xml.push_str("</kadry>\n");
+
+    file.write_all(xml.as_bytes()).unwrap();
+}
+
+pub fn write_roster_to_file(snapshot_name: &str, entries: &[RosterEntry]) {
+    let mut file = create_file(snapshot_name, ROSTER_OUTPUT_FILE);
+
+    entries.iter().for_each(|entry| {
+        let items = &[
+            entry.policeman_id.to_string(),
+            entry.date.to_string(),
+            entry.shift.code().to_string(),
+            entry
+                .absence
+                .map(|absence| absence.code())
+                .unwrap_or("SLUZBA")
+                .to_string(),
+        ];
+        write_to_file(&mut file, items);
+    });
+}
+
+pub fn write_absences_to_file<'a>(
+    snapshot_name: &str,
+    absences: impl Iterator<Item = &'a Absence>,
+) {
+    let mut file = create_file(snapshot_name, ABSENCES_OUTPUT_FILE);
+
+    absences.for_each(|absence| {
+        let items = &[
+            absence.policeman_id.to_string(),
+            absence.kind.code().to_string(),
+            absence.start_date.to_string(),
+            absence.end_date.to_string(),
+        ];
+        write_to_file(&mut file, items);
+    });
+}
+
 pub fn write_csv_vehicle_to_file(snapshot_name: &str, vehicles: &[Vehicle]) {
     let mut file = create_file(snapshot_name, VEHICLE_CSV_OUTPUT_FILE);
 
@@ -113,14 +588,68 @@ pub fn write_csv_vehicle_to_file(snapshot_name: &str, vehicles: &[Vehicle]) {
         let items = &[
             vehicle.id.to_string(),
             vehicle.registration_plate.to_string(),
-            vehicle.model.to_string(),
+            format!("{} {}", vehicle.model.make, vehicle.model.name),
             vehicle.manufacture_year.to_string(),
-            vehicle.seat_count.to_string(),
+            vehicle.model.seat_count.to_string(),
+            vehicle.commissioning_date.date_naive().to_string(),
+            optional_to_string(vehicle.decommissioning_date.map(|date| date.date_naive())),
+            format!("{:.0}", vehicle.odometer_km),
+            vehicle.model.fuel_type.to_string(),
         ];
         write_to_file(&mut file, items);
     });
 }
 
+/// The fleet-management system feed, which knows vehicles by its own fleet ids.
+pub fn write_fleet_vehicle_to_file(
+    snapshot_name: &str,
+    vehicles: &[Vehicle],
+    snapshot_date: DateTime<Utc>,
+) {
+    let mut file = create_file(snapshot_name, VEHICLE_FLEET_OUTPUT_FILE);
+    let stations = get_all_stations();
+
+    let vehicle_objects = vehicles
+        .iter()
+        .map(|vehicle| {
+            let status = match vehicle.state {
+                VehicleState::Available | VehicleState::Occupied => "ACTIVE",
+                VehicleState::OutOfService => "IN_REPAIR",
+                VehicleState::Decommissioned => "RETIRED",
+            };
+            let retired_at = vehicle
+                .decommissioning_date
+                .map(|date| format!("\"{}\"", date.to_rfc3339_opts(SecondsFormat::Secs, true)))
+                .unwrap_or("null".to_string());
+            format!(
+                "    {{\"fleetId\": \"{}\", \"registrationNumber\": \"{}\", \"make\": \"{}\", \
+                 \"model\": \"{}\", \"productionYear\": {}, \"mileageKm\": {:.0}, \
+                 \"homeStation\": \"{}\", \"status\": \"{}\", \"inServiceSince\": \"{}\", \
+                 \"retiredAt\": {}}}",
+                escape_json(&vehicle.fleet_id),
+                escape_json(&vehicle.registration_plate.replace(' ', "")),
+                escape_json(vehicle.model.make),
+                escape_json(vehicle.model.name),
+                vehicle.manufacture_year,
+                vehicle.odometer_km,
+                escape_json(stations[vehicle.station_id].name),
+                status,
+                vehicle
+                    .commissioning_date
+                    .to_rfc3339_opts(SecondsFormat::Secs, true),
+                retired_at,
+            )
+        })
+        .collect::<Vec<_>>();
+    let json = format!(
+        "{{\n  \"exportedAt\": \"{}\",\n  \"vehicles\": [\n{}\n  ]\n}}\n",
+        snapshot_date.to_rfc3339_opts(SecondsFormat::Secs, true),
+        vehicle_objects.join(",\n"),
+    );
+
+    file.write_all(json.as_bytes()).unwrap();
+}
+
 pub fn write_database_vehicle_to_file(snapshot_name: &str, vehicles: &[Vehicle]) {
     let mut file = create_file(snapshot_name, VEHICLE_DB_OUTPUT_FILE);
 
@@ -128,37 +657,124 @@ pub fn write_database_vehicle_to_file(snapshot_name: &str, vehicles: &[Vehicle])
         let items = &[
             vehicle.id.to_string(),
             vehicle.registration_plate.to_string(),
-            vehicle.vehicle_type.to_string(),
+            vehicle.model.vehicle_type.to_string(),
+            vehicle.station_id.to_string(),
+            vehicle.commissioning_date.to_string(),
+            optional_to_string(vehicle.decommissioning_date),
+            vehicle.audit.created_at.to_string(),
+            vehicle.audit.updated_at.to_string(),
+            vehicle.audit.created_by.to_string(),
         ];
         write_to_file(&mut file, items);
     });
 }
 
-pub fn write_patrols_to_file(
+pub fn write_maintenances_to_file(
     snapshot_name: &str,
-    patrols: &[Patrol],
+    maintenances: &[Maintenance],
     snapshot_date: DateTime<Utc>,
 ) {
-    let mut file = create_file(snapshot_name, PATROLS_OUTPUT_FILE);
+    let mut file = create_file(snapshot_name, MAINTENANCES_OUTPUT_FILE);
 
-    patrols.iter().for_each(|item| {
+    maintenances.iter().for_each(|maintenance| {
         let items = &[
-            item.id.to_string(),
-            item.vehicle_id.to_string(),
-            item.report_id.to_string(),
-            item.sending_time.to_string(),
-            datetime_if_happened!(item.arrival_time, snapshot_date),
-            datetime_if_happened!(item.finish_time, snapshot_date),
+            maintenance.id.to_string(),
+            maintenance.vehicle_id.to_string(),
+            maintenance.kind.code().to_string(),
+            maintenance.start_time.to_string(),
+            datetime_if_happened!(maintenance.end_time, snapshot_date),
         ];
         write_to_file(&mut file, items);
     });
 }
 
-pub fn write_policeman_patrol_to_file(snapshot_name: &str, patrols: &[Patrol]) {
+pub fn write_patrols_to_file(
+    snapshot_name: &str,
+    patrols: &[Patrol],
+    snapshot_date: DateTime<Utc>,
+) {
+    let mut file = create_file(snapshot_name, PATROLS_OUTPUT_FILE);
+
+    patrols
+        .iter()
+        .filter(|item| item.audit.created_at < snapshot_date)
+        .for_each(|item| {
+            let items = &[
+                item.id.to_string(),
+                item.vehicle_id.to_string(),
+                item.report_id.to_string(),
+                item.sending_time.to_string(),
+                datetime_if_happened!(item.arrival_time, snapshot_date),
+                datetime_if_happened!(item.finish_time, snapshot_date),
+                (item.sending_time - item.queued_time)
+                    .num_seconds()
+                    .to_string(),
+                item.station_id.to_string(),
+                format!("{:.3}", item.distance_km),
+                format!("{:.3}", item.fuel_used_l),
+                format!("{:.1}", item.odometer_km),
+                item.audit.created_at.to_string(),
+                item.audit.updated_at.to_string(),
+                item.audit.created_by.to_string(),
+            ];
+            write_to_file(&mut file, items);
+        });
+}
+
+pub fn write_outcomes_to_file(
+    snapshot_name: &str,
+    outcomes: &[PatrolOutcome],
+    patrols: &[Patrol],
+    snapshot_date: DateTime<Utc>,
+) {
+    let mut file = create_file(snapshot_name, OUTCOMES_OUTPUT_FILE);
+
+    outcomes
+        .iter()
+        .filter(|outcome| patrols[outcome.patrol_id].audit.created_at < snapshot_date)
+        .for_each(|outcome| {
+            let items = &[
+                outcome.patrol_id.to_string(),
+                outcome.kind.code().to_string(),
+                optional_to_string(outcome.fine_amount),
+            ];
+            write_to_file(&mut file, items);
+        });
+}
+
+pub fn write_involved_persons_to_file(
+    snapshot_name: &str,
+    involved_persons: &[InvolvedPerson],
+    patrols: &[Patrol],
+    snapshot_date: DateTime<Utc>,
+) {
+    let mut file = create_file(snapshot_name, INVOLVED_PERSONS_OUTPUT_FILE);
+
+    involved_persons
+        .iter()
+        .filter(|involved_person| {
+            patrols[involved_person.patrol_id].audit.created_at < snapshot_date
+        })
+        .for_each(|involved_person| {
+            let items = &[
+                involved_person.patrol_id.to_string(),
+                involved_person.person_id.to_string(),
+                involved_person.role.code().to_string(),
+            ];
+            write_to_file(&mut file, items);
+        });
+}
+
+pub fn write_policeman_patrol_to_file(
+    snapshot_name: &str,
+    patrols: &[Patrol],
+    snapshot_date: DateTime<Utc>,
+) {
     let mut file = create_file(snapshot_name, POLICEMEN_PATROLS_OUTPUT_FILE);
 
     patrols
         .iter()
+        .filter(|item| item.audit.created_at < snapshot_date)
         .flat_map(|item| {
             item.policemen_ids
                 .iter()
@@ -170,6 +786,399 @@ pub fn write_policeman_patrol_to_file(snapshot_name: &str, patrols: &[Patrol]) {
         });
 }
 
+pub fn write_warehouse_to_files(snapshot_name: &str, warehouse: &Warehouse) {
+    let mut file = create_file(snapshot_name, OFFICER_DIMENSION_OUTPUT_FILE);
+    warehouse.officers.iter().for_each(|row| {
+        let items = &[
+            row.key.to_string(),
+            row.policeman.person.id.to_string(),
+            row.policeman.service_number.to_string(),
+            row.policeman.person.first_name.to_string(),
+            row.last_name.to_string(),
+            row.policeman.rank.to_string(),
+            row.policeman.person.birth_date.date_naive().to_string(),
+            row.policeman.employment_date.date_naive().to_string(),
+            row.valid_from.to_string(),
+            optional_to_string(row.valid_to),
+            row.valid_to.is_none().to_string(),
+        ];
+        write_to_file(&mut file, items);
+    });
+
+    let mut file = create_file(snapshot_name, VEHICLE_DIMENSION_OUTPUT_FILE);
+    warehouse.vehicles.iter().for_each(|row| {
+        let items = &[
+            row.key.to_string(),
+            row.vehicle.id.to_string(),
+            row.vehicle.registration_plate.to_string(),
+            row.vehicle.model.make.to_string(),
+            row.vehicle.model.name.to_string(),
+            row.vehicle.model.vehicle_type.to_string(),
+            row.vehicle.model.fuel_type.to_string(),
+            row.vehicle.manufacture_year.to_string(),
+            row.vehicle.model.seat_count.to_string(),
+            row.vehicle.model.urban_l_per_100_km.to_string(),
+            row.vehicle.model.extra_urban_l_per_100_km.to_string(),
+            row.valid_from.to_string(),
+            optional_to_string(row.valid_to),
+            row.valid_to.is_none().to_string(),
+        ];
+        write_to_file(&mut file, items);
+    });
+
+    let mut file = create_file(snapshot_name, STATION_DIMENSION_OUTPUT_FILE);
+    warehouse.stations.iter().for_each(|row| {
+        let items = &[
+            row.key.to_string(),
+            row.station.id.to_string(),
+            row.station.name.to_string(),
+            row.station.city.to_string(),
+        ];
+        write_to_file(&mut file, items);
+    });
+
+    let mut file = create_file(snapshot_name, PLACE_DIMENSION_OUTPUT_FILE);
+    warehouse.places.iter().for_each(|row| {
+        let items = &[
+            row.key.to_string(),
+            row.place.id.to_string(),
+            row.place.city.to_string(),
+            row.place.street.to_string(),
+            format!("{:.6}", row.place.coordinates.latitude),
+            format!("{:.6}", row.place.coordinates.longitude),
+        ];
+        write_to_file(&mut file, items);
+    });
+
+    let mut file = create_file(snapshot_name, OUTCOME_DIMENSION_OUTPUT_FILE);
+    warehouse.outcomes.iter().for_each(|row| {
+        let items = &[row.key.to_string(), row.kind.code().to_string()];
+        write_to_file(&mut file, items);
+    });
+
+    let mut file = create_file(snapshot_name, REPORTER_DIMENSION_OUTPUT_FILE);
+    warehouse.reporters.iter().for_each(|row| {
+        let items = &[
+            row.key.to_string(),
+            row.person.id.to_string(),
+            row.person.first_name.to_string(),
+            row.person.last_name.to_string(),
+            row.person.birth_date.date_naive().to_string(),
+        ];
+        write_to_file(&mut file, items);
+    });
+
+    let mut file = create_file(snapshot_name, REPORT_TYPE_DIMENSION_OUTPUT_FILE);
+    warehouse.report_types.iter().for_each(|row| {
+        let items = &[row.key.to_string(), row.name.to_string()];
+        write_to_file(&mut file, items);
+    });
+
+    let mut file = create_file(snapshot_name, DATE_DIMENSION_OUTPUT_FILE);
+    warehouse.dates.iter().for_each(|row| {
+        let items = &[
+            row.key.to_string(),
+            row.date.to_string(),
+            row.date.day().to_string(),
+            row.day_of_week.to_string(),
+            row.day_name.to_string(),
+            row.week_of_year.to_string(),
+            row.iso_week.to_string(),
+            row.iso_year.to_string(),
+            row.date.month().to_string(),
+            row.month_name.to_string(),
+            row.quarter.to_string(),
+            row.date.year().to_string(),
+            row.is_weekend.to_string(),
+            row.holiday_name.is_some().to_string(),
+            optional_to_string(row.holiday_name),
+        ];
+        write_to_file(&mut file, items);
+    });
+
+    let mut file = create_file(snapshot_name, TIME_DIMENSION_OUTPUT_FILE);
+    warehouse.times.iter().for_each(|row| {
+        let items = &[
+            row.key.to_string(),
+            format!("{:02}:{:02}", row.hour, row.minute),
+            row.hour.to_string(),
+            row.minute.to_string(),
+            row.twelve_hour_shift.to_string(),
+            row.eight_hour_shift.to_string(),
+            row.part_of_day.to_string(),
+        ];
+        write_to_file(&mut file, items);
+    });
+
+    let mut file = create_file(snapshot_name, PATROL_FACT_OUTPUT_FILE);
+    warehouse.patrols.iter().for_each(|row| {
+        let items = &[
+            row.patrol_id.to_string(),
+            row.report_id.to_string(),
+            row.report_date_key.to_string(),
+            row.report_time_key.to_string(),
+            row.sending_date_key.to_string(),
+            row.sending_time_key.to_string(),
+            row.place_key.to_string(),
+            row.reporter_key.to_string(),
+            row.report_type_key.to_string(),
+            row.vehicle_key.to_string(),
+            row.responsible_station_key.to_string(),
+            row.sending_station_key.to_string(),
+            row.officer_keys.len().to_string(),
+            row.dispatch_delay_seconds.to_string(),
+            row.queue_wait_seconds.to_string(),
+            format!("{:.3}", row.distance_km),
+            format!("{:.3}", row.fuel_used_l),
+            optional_to_string(row.travel_seconds),
+            optional_to_string(row.on_scene_seconds),
+            optional_to_string(row.response_seconds),
+            optional_to_string(row.outcome_key),
+            optional_to_string(row.fine_amount),
+        ];
+        write_to_file(&mut file, items);
+    });
+
+    let mut file = create_file(snapshot_name, PATROL_OFFICER_BRIDGE_OUTPUT_FILE);
+    warehouse
+        .patrols
+        .iter()
+        .flat_map(|row| row.officer_keys.iter().map(|key| (row.patrol_id, key)))
+        .for_each(|(patrol_id, officer_key)| {
+            let items = &[patrol_id.to_string(), officer_key.to_string()];
+            write_to_file(&mut file, items);
+        });
+}
+
+/// Damages the already written source files and lists the defects in a side-car manifest.
+/// The same seed is used for every snapshot, so that unchanged rows are damaged the same way.
+pub fn inject_defects_into_files(snapshot_name: &str, rates: DefectRates, seed: u64) {
+    let mut injector = DefectInjector::new(rates, seed);
+    for (file_suffix, columns) in &DEFECT_FILES {
+        let rows = read_from_file(snapshot_name, file_suffix);
+        let rows = injector.inject(file_suffix, columns, source_key(file_suffix), rows);
+        let mut file = create_file(snapshot_name, file_suffix);
+        rows.iter().for_each(|row| write_to_file(&mut file, row));
+    }
+
+    let mut file = create_file(snapshot_name, DEFECTS_MANIFEST_OUTPUT_FILE);
+    injector.defects.iter().for_each(|defect| {
+        let items = &[
+            snapshot_name.to_string().add(defect.file_name),
+            defect.row.to_string(),
+            optional_to_string(defect.column),
+            defect.kind.code().to_string(),
+            defect.original_value.to_string(),
+        ];
+        write_to_file(&mut file, items);
+    });
+}
+
+/// Writes the spreadsheet source files, which must be in the default format, as sheets of a workbook.
+/// Values which cannot be parsed, e.g. damaged by the defect injector, are written as text.
+pub fn write_spreadsheets_to_file(snapshot_name: &str, styled_headers: bool) {
+    let mut workbook = Workbook::new();
+    let header_format = if styled_headers {
+        Format::new()
+            .set_bold()
+            .set_background_color(Color::Silver)
+            .set_border_bottom(FormatBorder::Thin)
+    } else {
+        Format::new()
+    };
+    let date_format = Format::new().set_num_format(SPREADSHEET_DATE_FORMAT);
+
+    for sheet in &SHEETS {
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(sheet.name).unwrap();
+        for (column, header) in sheet.headers.iter().enumerate() {
+            worksheet
+                .write_string_with_format(0, column as u16, *header, &header_format)
+                .unwrap();
+        }
+        worksheet.set_freeze_panes(1, 0).unwrap();
+
+        for (index, row) in read_from_file(snapshot_name, sheet.file_suffix)
+            .iter()
+            .enumerate()
+        {
+            let row_number = index as u32 + 1;
+            for (column, value) in row
+                .iter()
+                .enumerate()
+                .filter(|(_, value)| !value.is_empty())
+            {
+                let column_number = column as u16;
+                let date = sheet
+                    .dates
+                    .contains(&column)
+                    .then(|| NaiveDate::parse_from_str(value, ISO_DATE_FORMAT).ok())
+                    .flatten()
+                    .and_then(|date| {
+                        ExcelDateTime::from_ymd(
+                            date.year() as u16,
+                            date.month() as u8,
+                            date.day() as u8,
+                        )
+                        .ok()
+                    });
+                let number = sheet
+                    .numbers
+                    .contains(&column)
+                    .then(|| value.parse::<f64>().ok())
+                    .flatten();
+                if let Some(date) = date {
+                    worksheet
+                        .write_datetime_with_format(row_number, column_number, &date, &date_format)
+                        .unwrap();
+                } else if let Some(number) = number {
+                    worksheet
+                        .write_number(row_number, column_number, number)
+                        .unwrap();
+                } else {
+                    worksheet
+                        .write_string(row_number, column_number, value)
+                        .unwrap();
+                }
+            }
+        }
+        worksheet.autofit();
+    }
+
+    workbook
+        .save_to_writer(create_file(snapshot_name, SPREADSHEETS_OUTPUT_FILE))
+        .unwrap();
+}
+
+/// Rewrites the source files, which are written in the default format, in the format of their source.
+pub fn apply_source_formats_to_files(snapshot_name: &str, formats: SourceFormats) {
+    for (file_suffix, source, columns) in &SOURCE_FILES {
+        let format = formats.get(*source);
+        let mut text = String::new();
+        for mut row in read_from_file(snapshot_name, file_suffix) {
+            format.reformat_row(columns, &mut row);
+            text.push_str(&row.join(format.column_delimiter()));
+            text.push_str(format.line_ending());
+        }
+        let mut file = create_file(snapshot_name, file_suffix);
+        file.write_all(&format.encode(&text)).unwrap();
+    }
+}
+
+/// Returns the paths of the written files.
+pub fn write_statistics_to_files(snapshot_name: &str, statistics: &Statistics) -> [String; 2] {
+    let counts_to_json = |counts: &[(String, usize)]| {
+        counts
+            .iter()
+            .map(|(name, count)| format!("\"{}\": {count}", escape_json(name)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let percentiles_to_json = |values: &[f64]| {
+        PERCENTILES
+            .iter()
+            .zip(values)
+            .map(|(percentile, value)| format!("\"p{percentile}\": {value:.0}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let reports_by_type = statistics
+        .reports_by_type
+        .iter()
+        .map(|(report_type, count)| (report_type.to_string(), *count))
+        .collect::<Vec<_>>();
+    let json = format!(
+        "{{\n  \"snapshotDate\": \"{}\",\n  \"reportsByType\": {{{}}},\n  \
+         \"reportsByMonth\": {{{}}},\n  \"responseTimeSeconds\": {{{}}},\n  \
+         \"dispatchWaitSeconds\": {{{}}},\n  \"meanDispatchWaitSeconds\": {:.1},\n  \
+         \"officerUtilization\": {:.4},\n  \"vehicleUtilization\": {:.4},\n  \
+         \"headcountByMonth\": {{{}}}\n}}\n",
+        statistics
+            .snapshot_date
+            .to_rfc3339_opts(SecondsFormat::Secs, true),
+        counts_to_json(&reports_by_type),
+        counts_to_json(&statistics.reports_by_month),
+        percentiles_to_json(&statistics.response_time_percentiles),
+        percentiles_to_json(&statistics.dispatch_wait_percentiles),
+        statistics.mean_dispatch_wait_seconds,
+        statistics.officer_utilization,
+        statistics.vehicle_utilization,
+        counts_to_json(&statistics.headcount_by_month),
+    );
+    let mut file = create_file(snapshot_name, STATISTICS_JSON_OUTPUT_FILE);
+    file.write_all(json.as_bytes()).unwrap();
+
+    let mut file = create_file(snapshot_name, STATISTICS_TEXT_OUTPUT_FILE);
+    file.write_all(statistics.to_string().as_bytes()).unwrap();
+
+    [STATISTICS_TEXT_OUTPUT_FILE, STATISTICS_JSON_OUTPUT_FILE].map(|file_suffix| {
+        PLACES_OUTPUT_DIRECTORY
+            .to_owned()
+            .add(snapshot_name)
+            .add(file_suffix)
+    })
+}
+
+fn source_key(file_suffix: &str) -> &'static [usize] {
+    let Some((_, key_columns)) = SOURCE_KEYS
+        .iter()
+        .find(|(source_file_suffix, _)| *source_file_suffix == file_suffix)
+    else {
+        panic!("Every source file should be listed in SOURCE_KEYS. Missing file \"{file_suffix}\"");
+    };
+    key_columns
+}
+
+/// Reads a source file written in the format of its source, converting it back to the default one.
+pub fn read_source_file(
+    snapshot_name: &str,
+    file_suffix: &str,
+    formats: SourceFormats,
+) -> Vec<Vec<String>> {
+    let Some((_, source, columns)) = SOURCE_FILES
+        .iter()
+        .find(|(source_file_suffix, _, _)| *source_file_suffix == file_suffix)
+    else {
+        panic!(
+            "Every source file should be listed in SOURCE_FILES. Missing file \"{file_suffix}\""
+        );
+    };
+    let format = formats.get(*source);
+    let path_string = PLACES_OUTPUT_DIRECTORY
+        .to_owned()
+        .add(snapshot_name)
+        .add(file_suffix);
+    format
+        .decode(&read(path_string).unwrap())
+        .lines()
+        .map(|line| {
+            let mut row = line
+                .split(format.column_delimiter())
+                .map(str::to_string)
+                .collect::<Vec<_>>();
+            format.normalize_row(columns, &mut row);
+            row
+        })
+        .collect()
+}
+
+fn optional_to_string<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn escape_json(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
 fn create_file(snapshot_name: &str, file_suffix: &str) -> File {
     let path_string = PLACES_OUTPUT_DIRECTORY
         .to_owned()
@@ -184,8 +1193,20 @@ fn create_file(snapshot_name: &str, file_suffix: &str) -> File {
         .unwrap()
 }
 
+fn read_from_file(snapshot_name: &str, file_suffix: &str) -> Vec<Vec<String>> {
+    let path_string = PLACES_OUTPUT_DIRECTORY
+        .to_owned()
+        .add(snapshot_name)
+        .add(file_suffix);
+    read_to_string(path_string)
+        .unwrap()
+        .lines()
+        .map(|line| line.split(COLUMN_DELIMITER).map(str::to_string).collect())
+        .collect()
+}
+
 fn write_to_file(file: &mut File, items: &[String]) {
-    file.write_all(items.get(0).unwrap().to_string().as_bytes())
+    file.write_all(items.first().unwrap().to_string().as_bytes())
         .unwrap();
 
     items.iter().skip(1).for_each(|item| {
@@ -195,3 +1216,178 @@ fn write_to_file(file: &mut File, items: &[String]) {
 
     file.write_all("\n".as_bytes()).unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_file;
+    use std::ops::Add;
+
+    use chrono::{Duration, TimeZone, Utc};
+    use rand::thread_rng;
+
+    use crate::audit::Audit;
+    use crate::citizen::CitizenRegistry;
+    use crate::dispatch::PatrolRequest;
+    use crate::io::{
+        read_from_file, source_key, write_csv_policemen_to_file, write_database_policemen_to_file,
+        write_database_vehicle_to_file, write_patrols_to_file, write_places_to_file,
+        write_policeman_patrol_to_file, write_reports_to_file, DEFECT_FILES, PATROLS_OUTPUT_FILE,
+        PLACES_OUTPUT_DIRECTORY, PLACES_OUTPUT_FILE, POLICEMEN_CSV_OUTPUT_FILE,
+        POLICEMEN_DB_OUTPUT_FILE, POLICEMEN_PATROLS_OUTPUT_FILE, REPORTS_OUTPUT_FILE,
+        VEHICLE_DB_OUTPUT_FILE,
+    };
+    use crate::patrol::Patrol;
+    use crate::person::Policeman;
+    use crate::place::get_all_places;
+    use crate::report::Report;
+    use crate::source_format::{SourceFormat, SourceFormats};
+    use crate::station::get_all_stations;
+    use crate::validator::validate_snapshot;
+    use crate::vehicle::Vehicle;
+
+    const TEST_SNAPSHOT_NAME: &str = "TEST_";
+    const VALIDATION_TEST_SNAPSHOT_NAME: &str = "TEST_VALIDATION_";
+
+    #[test]
+    fn should_write_soft_deleted_reports_and_drop_hard_deleted_ones() {
+        let mut generator = thread_rng();
+        let time = Utc.with_ymd_and_hms(2023, 6, 5, 12, 0, 0).unwrap();
+        let mut citizens = CitizenRegistry::default();
+        let mut reports = (0..3)
+            .map(|id| {
+                let reporter_id = citizens.choose_reporter(&mut generator, time);
+                Report::generate_with_time_reporter_and_id(
+                    &mut generator,
+                    time,
+                    10,
+                    citizens.get(reporter_id),
+                    id,
+                )
+            })
+            .collect::<Vec<_>>();
+        reports[1].is_deleted = true;
+        reports[2].is_deleted = true;
+        reports[2].is_hard_deleted = true;
+
+        // Later than any of the reports could have been recorded
+        let snapshot_date = time + Duration::days(30);
+        write_reports_to_file(TEST_SNAPSHOT_NAME, &reports, &citizens, snapshot_date);
+        let rows = read_from_file(TEST_SNAPSHOT_NAME, REPORTS_OUTPUT_FILE);
+        remove_file(
+            PLACES_OUTPUT_DIRECTORY
+                .to_owned()
+                .add(TEST_SNAPSHOT_NAME)
+                .add(REPORTS_OUTPUT_FILE),
+        )
+        .unwrap();
+
+        let ids_and_deletions = rows
+            .iter()
+            .map(|row| (row[0].as_str(), row[9].as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(ids_and_deletions, vec![("0", "false"), ("1", "true")]);
+    }
+
+    #[test]
+    fn should_pass_validation_of_a_defect_free_snapshot_with_a_late_report() {
+        let mut generator = thread_rng();
+        let start_time = Utc.with_ymd_and_hms(2023, 6, 1, 0, 0, 0).unwrap();
+        let time = start_time + Duration::days(1);
+        let places = get_all_places();
+        let station = get_all_stations().first().unwrap();
+        let policemen = (0..2)
+            .map(|id| {
+                Policeman::generate_just_employed_with_id_and_station(
+                    &mut generator,
+                    &start_time,
+                    id,
+                    station.id,
+                )
+            })
+            .collect::<Vec<_>>();
+        let vehicles = vec![Vehicle::generate_new_with_id_and_station(
+            &mut generator,
+            0,
+            station,
+            start_time,
+        )];
+        let mut citizens = CitizenRegistry::default();
+        let reporter_id = citizens.choose_reporter(&mut generator, time);
+        let mut report = Report::generate_with_time_reporter_and_id(
+            &mut generator,
+            time,
+            places.len(),
+            citizens.get(reporter_id),
+            0,
+        );
+        // Entered days after the patrol was sent
+        report.audit = Audit::generate_with_time(&mut generator, time + Duration::hours(100));
+        let request = PatrolRequest {
+            report_id: report.id,
+            place_id: report.place_id,
+            station_id: report.station_id,
+            priority: 0,
+            requested_time: time,
+        };
+        let patrols = vec![
+            Patrol::generate_with_request_policemen_vehicle_and_sending_time_and_id(
+                &mut generator,
+                &request,
+                &report,
+                vec![0, 1],
+                &vehicles[0],
+                time,
+                0,
+            ),
+        ];
+        let formats = SourceFormats {
+            database: SourceFormat::DEFAULT,
+            dispatch: SourceFormat::DEFAULT,
+            call_center: SourceFormat::DEFAULT,
+            policemen_spreadsheet: SourceFormat::DEFAULT,
+            vehicle_spreadsheet: SourceFormat::DEFAULT,
+        };
+
+        for snapshot_date in [time + Duration::hours(50), time + Duration::hours(200)] {
+            let snapshot_name = VALIDATION_TEST_SNAPSHOT_NAME;
+            write_places_to_file(snapshot_name, places);
+            write_reports_to_file(snapshot_name, &[report], &citizens, snapshot_date);
+            write_database_policemen_to_file(snapshot_name, &policemen);
+            write_csv_policemen_to_file(snapshot_name, &policemen, snapshot_date);
+            write_database_vehicle_to_file(snapshot_name, &vehicles);
+            write_patrols_to_file(snapshot_name, &patrols, snapshot_date);
+            write_policeman_patrol_to_file(snapshot_name, &patrols, snapshot_date);
+
+            let violations = validate_snapshot(snapshot_name, formats);
+            for file_suffix in [
+                PLACES_OUTPUT_FILE,
+                REPORTS_OUTPUT_FILE,
+                POLICEMEN_DB_OUTPUT_FILE,
+                POLICEMEN_CSV_OUTPUT_FILE,
+                VEHICLE_DB_OUTPUT_FILE,
+                PATROLS_OUTPUT_FILE,
+                POLICEMEN_PATROLS_OUTPUT_FILE,
+            ] {
+                remove_file(
+                    PLACES_OUTPUT_DIRECTORY
+                        .to_owned()
+                        .add(snapshot_name)
+                        .add(file_suffix),
+                )
+                .unwrap();
+            }
+            assert_eq!(violations, vec![]);
+        }
+    }
+
+    #[test]
+    fn should_never_inject_orphan_foreign_keys_into_key_columns() {
+        for (file_suffix, columns) in &DEFECT_FILES {
+            let key_columns = source_key(file_suffix);
+            assert!(columns
+                .foreign_keys
+                .iter()
+                .all(|column| !key_columns.contains(column)));
+        }
+    }
+}
@@ -1,43 +1,138 @@
+use crate::arrival::generate_next_report_time;
+use crate::calendar::{Calendar, DAY_SHIFT_START_HOUR};
+use crate::citizen::CitizenRegistry;
+use crate::defect::DefectRates;
+use crate::dispatch::{DispatchQueue, PatrolRequest};
+use crate::dispatch_strategy::DispatchStrategyKind;
 use crate::event::{Event, EventAction};
 use crate::io::{
+    apply_source_formats_to_files, inject_defects_into_files, write_absences_to_file,
     write_csv_policemen_to_file, write_csv_vehicle_to_file, write_database_policemen_to_file,
-    write_database_vehicle_to_file, write_patrols_to_file, write_places_to_file,
-    write_policeman_patrol_to_file, write_reports_to_file,
+    write_database_vehicle_to_file, write_fleet_vehicle_to_file, write_hr_policemen_to_file,
+    write_involved_persons_to_file, write_maintenances_to_file, write_outcomes_to_file,
+    write_patrols_to_file, write_persons_to_file, write_places_to_file,
+    write_policeman_patrol_to_file, write_reports_to_file, write_roster_to_file,
+    write_spreadsheets_to_file, write_stations_to_file, write_statistics_to_files,
+    write_warehouse_to_files,
 };
+use crate::outcome::PatrolOutcome;
 use crate::patrol::Patrol;
 use crate::person::{Policeman, PolicemanState};
 use crate::place::get_all_places;
-use crate::report::Report;
-use chrono::{Days, Duration, TimeZone, Utc};
+use crate::report::{get_report_type_parameters, Report};
+use crate::roster::{Roster, SHIFT_LENGTH_HOURS};
+use crate::snapshot_diff::{diff_snapshots, ChangeKind};
+use crate::source_format::{DateFormat, LineEnding, SourceFormat, SourceFormats, TextEncoding};
+use crate::station::get_all_stations;
+use crate::statistics::Statistics;
+use crate::validator::validate_snapshot;
+use chrono::{Days, Duration, TimeZone, Timelike, Utc};
 use rand::prelude::IteratorRandom;
 use rand::{thread_rng, Rng};
 use sorted_vec::SortedVec;
+use std::collections::{BTreeSet, HashSet};
+use std::env;
+use std::process::exit;
 
-use crate::vehicle::{Vehicle, VehicleState};
+use crate::vehicle::{
+    generate_procurement_time, Maintenance, MaintenanceKind, Vehicle, VehicleState,
+    DAYS_BETWEEN_SERVICES,
+};
+use crate::warehouse::Warehouse;
 
+mod arrival;
+mod audit;
+mod calendar;
+mod citizen;
+mod defect;
+mod dispatch;
+mod dispatch_strategy;
 mod event;
 mod io;
+mod outcome;
 mod patrol;
 mod person;
 mod place;
 mod report;
+mod roster;
+mod snapshot_diff;
+mod source_format;
+mod station;
+mod statistics;
 mod string_occurrences;
+mod validator;
 mod vehicle;
+mod warehouse;
 
-// 10 minutes
-const MIN_SECONDS_BETWEEN_REPORTS: i64 = 3000;
-// 40 minutes
-const MAX_SECONDS_BETWEEN_REPORTS: i64 = 12000;
-// 5 minutes
-const MIN_REPORT_TO_SENDING_SECONDS: i64 = 300;
-// 15 minutes
-const MAX_REPORT_TO_SENDING_SECONDS: i64 = 900;
-const POLICEMEN_COUNT: usize = 80;
-const VEHICLES_COUNT: usize = 60;
 const POLICEMAN_LAST_NAME_CHANGE_EVENTS_COUNT: i64 = 20;
-const TWO_PATROLS_CHANCE: f64 = 0.1;
+// Set all the rates to 0 to export clean data
+const DEFECT_RATES: DefectRates = DefectRates {
+    duplicated_row: 0.002,
+    missing_field: 0.002,
+    typo: 0.005,
+    swapped_names: 0.002,
+    malformed_pesel: 0.002,
+    malformed_phone_number: 0.002,
+    out_of_range_date: 0.001,
+    orphan_foreign_key: 0.001,
+};
+// Set all the formats to SourceFormat::DEFAULT to export uniform data
+const SOURCE_FORMATS: SourceFormats = SourceFormats {
+    database: SourceFormat::DEFAULT,
+    dispatch: SourceFormat {
+        date_format: DateFormat::EpochSeconds,
+        decimal_separator: '.',
+        encoding: TextEncoding::Utf8,
+        line_ending: LineEnding::Lf,
+    },
+    call_center: SourceFormat {
+        date_format: DateFormat::WarsawLocal,
+        decimal_separator: '.',
+        encoding: TextEncoding::Utf8,
+        line_ending: LineEnding::CrLf,
+    },
+    policemen_spreadsheet: SourceFormat {
+        date_format: DateFormat::Polish,
+        decimal_separator: ',',
+        encoding: TextEncoding::Windows1250,
+        line_ending: LineEnding::CrLf,
+    },
+    vehicle_spreadsheet: SourceFormat {
+        date_format: DateFormat::ExcelSerial,
+        decimal_separator: ',',
+        encoding: TextEncoding::Windows1250,
+        line_ending: LineEnding::CrLf,
+    },
+};
+// Headers of the spreadsheets workbook are bold and shaded
+const STYLE_SPREADSHEET_HEADERS: bool = true;
+const VEHICLE_EVENT_RETRY_MINUTES: i64 = 60;
+const USAGE: &str = "Usage: pg_hd_data_generator [--dispatch-strategy random|least-recently-used|\
+least-workload|stable-partners|nearest-unit | validate SNAPSHOT_NAME | \
+diff OLD_SNAPSHOT_NAME NEW_SNAPSHOT_NAME]";
+const DISPATCH_STRATEGY_OPTION: &str = "--dispatch-strategy";
+// 1 week
+const DAYS_TO_REPLACE_RESIGNED_POLICEMAN: u64 = 7;
+// Used unless another one is chosen with DISPATCH_STRATEGY_OPTION
+const DEFAULT_DISPATCH_STRATEGY: DispatchStrategyKind = DispatchStrategyKind::Random;
 
 fn main() {
+    let arguments = env::args().skip(1).collect::<Vec<_>>();
+    let dispatch_strategy_kind = match arguments.as_slice() {
+        [] => DEFAULT_DISPATCH_STRATEGY,
+        [option, code] if option == DISPATCH_STRATEGY_OPTION => {
+            let Some(kind) = DispatchStrategyKind::from_code(code) else {
+                eprintln!("{USAGE}");
+                exit(2);
+            };
+            kind
+        }
+        [command, arguments @ ..] => {
+            run_command(command, arguments);
+            return;
+        }
+    };
+
     let data_start_date = Utc.with_ymd_and_hms(2015, 6, 1, 0, 0, 0).unwrap();
     let snapshots = [
         (
@@ -52,18 +147,52 @@ fn main() {
         ),
     ];
 
+    let data_end_date = snapshots.last().unwrap().2;
+    let calendar = Calendar::from_range(data_start_date, data_end_date);
+
     let mut generator = thread_rng();
     let places = get_all_places();
-    let mut policemen = (0..POLICEMEN_COUNT)
-        .map(|index| {
-            Policeman::generate_just_employed_with_id(&mut generator, &data_start_date, index)
+    let stations = get_all_stations();
+    let mut policemen = stations
+        .iter()
+        .flat_map(|station| (0..station.policemen_count).map(|_| station.id))
+        .enumerate()
+        .map(|(index, station_id)| {
+            Policeman::generate_just_employed_with_id_and_station(
+                &mut generator,
+                &data_start_date,
+                index,
+                station_id,
+            )
         })
         .collect::<Vec<_>>();
-    let mut vehicles = (0..VEHICLES_COUNT)
-        .map(|index| Vehicle::generate_with_id(&mut generator, index))
+    let mut roster = Roster::default();
+    for policeman in &policemen {
+        roster.add_policeman(&mut generator, policeman, data_end_date);
+    }
+    let mut vehicles = stations
+        .iter()
+        .flat_map(|station| (0..station.vehicles_count).map(move |_| station))
+        .enumerate()
+        .map(|(index, station)| {
+            Vehicle::generate_used_with_id_and_station(
+                &mut generator,
+                index,
+                station,
+                data_start_date,
+            )
+        })
         .collect::<Vec<_>>();
+    let mut citizens = CitizenRegistry::default();
     let mut reports = vec![];
     let mut patrols = vec![];
+    let mut outcomes = vec![];
+    let mut involved_persons = vec![];
+    let mut last_name_changes = vec![];
+    let mut maintenances: Vec<Maintenance> = vec![];
+    let mut dispatch_queue = DispatchQueue::default();
+    let mut dispatch_strategy = dispatch_strategy_kind.create(places);
+    let defect_seed = generator.gen::<u64>();
 
     let resignation_events = policemen
         .iter()
@@ -78,7 +207,7 @@ fn main() {
 
     let last_name_change_events = (0..POLICEMAN_LAST_NAME_CHANGE_EVENTS_COUNT).map(|i| Event {
         time: snapshots
-            .get(0)
+            .first()
             .unwrap()
             .2
             .checked_add_signed(Duration::days(i))
@@ -98,57 +227,90 @@ fn main() {
         time: data_start_date,
         action: EventAction::Report,
     });
+    for vehicle in &vehicles {
+        for event in Event::from_vehicle_commissioning(&mut generator, vehicle) {
+            events.push(event);
+        }
+    }
+    events.push(Event {
+        time: data_start_date.with_hour(DAY_SHIFT_START_HOUR).unwrap(),
+        action: EventAction::ShiftChange,
+    });
 
-    loop {
-        let Some(event) = events.get(0) else {
-            break;
-        };
-
+    while let Some(event) = events.first() {
         let event = *event;
         events.remove_index(0);
         let current_time = event.time;
 
         match event.action {
-            EventAction::PolicemanEmployment => {
-                let policeman = Policeman::generate_just_employed_with_id(
+            EventAction::PolicemanEmployment(station_id) => {
+                let policeman = Policeman::generate_just_employed_with_id_and_station(
                     &mut generator,
                     &current_time,
                     policemen.len(),
+                    station_id,
                 );
                 policemen.push(policeman);
+                roster.add_policeman(&mut generator, &policeman, data_end_date);
                 let event = Event::from_policeman_resignation_event(&policeman);
                 events.push(event);
+                events.push(Event {
+                    time: current_time,
+                    action: EventAction::Dispatch,
+                });
             }
-            EventAction::PolicemanResignation => {
-                let next_policeman_employment_date =
-                    current_time.checked_add_days(Days::new(7)).unwrap();
+            EventAction::PolicemanResignation(policeman_id) => {
+                let policeman = policemen.get_mut(policeman_id).unwrap();
+                policeman.audit.touch(current_time);
+                // An occupied policeman resigns once the patrol is finished
+                if policeman.state == PolicemanState::Available {
+                    policeman.state = PolicemanState::Resigned;
+                }
+
+                let next_policeman_employment_date = current_time
+                    .checked_add_days(Days::new(DAYS_TO_REPLACE_RESIGNED_POLICEMAN))
+                    .unwrap();
                 let event = Event {
                     time: next_policeman_employment_date,
-                    action: EventAction::PolicemanEmployment,
+                    action: EventAction::PolicemanEmployment(policeman.station_id),
                 };
                 events.push(event);
             }
             EventAction::Report => {
                 let report_id = reports.len();
-                let report = Report::generate_with_time_and_id(
+                let reporter_id = citizens.choose_reporter(&mut generator, current_time);
+                let report = Report::generate_with_time_reporter_and_id(
                     &mut generator,
                     current_time,
                     places.len(),
+                    citizens.get(reporter_id),
                     reports.len(),
                 );
                 reports.push(report);
+                if let Some(time) = report.generate_correction_time(&mut generator) {
+                    events.push(Event {
+                        time,
+                        action: EventAction::ReportCorrection(report_id),
+                    });
+                }
+                if let Some(duplicate) =
+                    report.generate_erroneous_duplicate_with_id(&mut generator, reports.len())
+                {
+                    events.push(Event {
+                        time: duplicate.generate_deletion_time(&mut generator),
+                        action: EventAction::ReportDeletion(duplicate.id),
+                    });
+                    reports.push(duplicate);
+                }
 
-                let time_before_sending_patrol = generator
-                    .gen_range(MIN_REPORT_TO_SENDING_SECONDS..MAX_REPORT_TO_SENDING_SECONDS);
+                let parameters = get_report_type_parameters(report.reported_type);
+                let time_before_sending_patrol =
+                    parameters.generate_dispatch_delay_seconds(&mut generator);
                 let next_report_time = current_time
                     .checked_add_signed(Duration::seconds(time_before_sending_patrol))
                     .unwrap();
 
-                let required_patrols = if generator.gen_bool(TWO_PATROLS_CHANCE) {
-                    2
-                } else {
-                    1
-                };
+                let required_patrols = parameters.generate_patrols_count(&mut generator);
                 for _ in 0..required_patrols {
                     events.push(Event {
                         time: next_report_time,
@@ -156,80 +318,135 @@ fn main() {
                     });
                 }
 
-                let time_between_reports =
-                    generator.gen_range(MIN_SECONDS_BETWEEN_REPORTS..MAX_SECONDS_BETWEEN_REPORTS);
-                let next_report_time = current_time
-                    .checked_add_signed(Duration::seconds(time_between_reports))
-                    .unwrap();
+                let next_report_time = generate_next_report_time(&mut generator, current_time);
                 let event = Event {
                     time: next_report_time,
                     action: EventAction::Report,
                 };
                 events.push(event);
             }
+            EventAction::ReportCorrection(report_id) => {
+                reports.get_mut(report_id).unwrap().correct(current_time);
+            }
+            EventAction::ReportDeletion(report_id) => {
+                reports
+                    .get_mut(report_id)
+                    .unwrap()
+                    .delete(&mut generator, current_time);
+            }
             EventAction::SendPatrol(report_id) => {
-                const TARGET_POLICEMEN_COUNT: usize = 2;
-                let mut chosen_policemen = policemen
-                    .iter_mut()
-                    .filter(|policeman| policeman.state == PolicemanState::Available)
-                    .choose_multiple(&mut generator, TARGET_POLICEMEN_COUNT);
+                let report = reports.get(report_id).unwrap();
+                dispatch_queue.push(PatrolRequest {
+                    report_id,
+                    place_id: report.place_id,
+                    station_id: report.station_id,
+                    priority: get_report_type_parameters(report.reported_type).priority,
+                    requested_time: current_time,
+                });
+                events.push(Event {
+                    time: current_time,
+                    action: EventAction::Dispatch,
+                });
+            }
+            EventAction::Dispatch => {
+                // Requests which cannot be served now are skipped, so that they do not block the
+                // ones behind them
+                while let Some((request, (policemen_ids, vehicle_id))) = dispatch_queue
+                    .pop_servable(|request| {
+                        let report = reports.get(request.report_id).unwrap();
+                        let target_policemen_count =
+                            get_report_type_parameters(report.reported_type).policemen_per_patrol;
+                        let responsible_station = stations.get(request.station_id).unwrap();
+                        // Neighbouring stations provide mutual aid when the responsible one is
+                        // saturated
+                        for station in responsible_station.with_neighbours() {
+                            let available_policemen = policemen
+                                .iter()
+                                .filter(|policeman| policeman.station_id == station.id)
+                                .filter(|policeman| policeman.state == PolicemanState::Available)
+                                .filter(|policeman| {
+                                    roster.is_on_duty(policeman.person.id, current_time)
+                                })
+                                .collect::<Vec<_>>();
+                            let available_vehicles = vehicles
+                                .iter()
+                                .filter(|vehicle| vehicle.station_id == station.id)
+                                .filter(|vehicle| vehicle.can_carry_patrol(target_policemen_count))
+                                .collect::<Vec<_>>();
+                            if available_policemen.len() < target_policemen_count
+                                || available_vehicles.is_empty()
+                            {
+                                continue;
+                            }
 
-                if chosen_policemen.len() < TARGET_POLICEMEN_COUNT {
-                    let time_before_sending_patrol = generator
-                        .gen_range(MIN_REPORT_TO_SENDING_SECONDS..MAX_REPORT_TO_SENDING_SECONDS);
-                    let next_report_time = current_time
-                        .checked_add_signed(Duration::seconds(time_before_sending_patrol))
-                        .unwrap();
+                            let policemen_ids = dispatch_strategy.choose_policemen(
+                                &mut generator,
+                                &available_policemen,
+                                target_policemen_count,
+                                report,
+                            );
+                            let vehicle_id = dispatch_strategy.choose_vehicle(
+                                &mut generator,
+                                &available_vehicles,
+                                report,
+                            );
+                            if let (true, Some(vehicle_id)) =
+                                (policemen_ids.len() == target_policemen_count, vehicle_id)
+                            {
+                                return Some((policemen_ids, vehicle_id));
+                            }
+                        }
+                        None
+                    })
+                {
+                    let report = reports.get(request.report_id).unwrap();
+                    for policeman_id in &policemen_ids {
+                        policemen.get_mut(*policeman_id).unwrap().state = PolicemanState::Occupied;
+                    }
+                    vehicles.get_mut(vehicle_id).unwrap().state = VehicleState::Occupied;
+                    let patrol_id = patrols.len();
+                    let patrol =
+                        Patrol::generate_with_request_policemen_vehicle_and_sending_time_and_id(
+                            &mut generator,
+                            &request,
+                            report,
+                            policemen_ids,
+                            vehicles.get(vehicle_id).unwrap(),
+                            current_time,
+                            patrol_id,
+                        );
+
+                    vehicles.get_mut(vehicle_id).unwrap().odometer_km += patrol.distance_km;
+                    dispatch_strategy.on_patrol_sent(&patrol, report);
                     events.push(Event {
-                        time: next_report_time,
-                        action: EventAction::SendPatrol(report_id),
+                        time: patrol.arrival_time,
+                        action: EventAction::PatrolArrival(patrol_id),
                     });
-                    continue;
+                    events.push(Event {
+                        time: patrol.finish_time,
+                        action: EventAction::FinishedPatrol(patrol_id),
+                    });
+                    patrols.push(patrol);
                 }
-
-                let chosen_vehicle = vehicles
-                    .iter_mut()
-                    .filter(|vehicle| vehicle.state == VehicleState::Available)
-                    .choose(&mut generator);
-
-                let Some(chosen_vehicle) = chosen_vehicle else {
-                    let time_before_sending_patrol = generator.gen_range(MIN_REPORT_TO_SENDING_SECONDS..MAX_REPORT_TO_SENDING_SECONDS);
-                    let next_report_time = current_time.checked_add_signed(Duration::seconds(time_before_sending_patrol)).unwrap();
-                    events.push(Event { time: next_report_time, action: EventAction::SendPatrol(report_id) });
-                    continue;
-                };
-
-                let policemen_ids = chosen_policemen
-                    .iter_mut()
-                    .map(|policeman| {
-                        policeman.state = PolicemanState::Occupied;
-                        policeman.person.id
-                    })
-                    .collect::<Vec<_>>();
-
-                chosen_vehicle.state = VehicleState::Occupied;
-                let vehicle_id = chosen_vehicle.id;
-                let patrol_id = patrols.len();
-                let patrol =
-                    Patrol::generate_with_report_id_policemen_vehicles_and_sending_time_and_id(
-                        &mut generator,
-                        report_id,
-                        policemen_ids,
-                        vehicle_id,
-                        current_time,
-                        patrol_id,
-                    );
-
-                events.push(Event {
-                    time: patrol.finish_time,
-                    action: EventAction::FinishedPatrol(patrol_id),
-                });
-                patrols.push(patrol);
+            }
+            EventAction::PatrolArrival(patrol_id) => {
+                // The arrival time is filled in by the patrol
+                patrols
+                    .get_mut(patrol_id)
+                    .unwrap()
+                    .audit
+                    .touch(current_time);
             }
             EventAction::FinishedPatrol(patrol_id) => {
-                let Some(patrol) = patrols.get(patrol_id) else {
-                    panic!("A patrol_id ({}) should always point to a valid patrol. Patrol count = {}", patrol_id, patrols.len());
+                let Some(patrol) = patrols.get_mut(patrol_id) else {
+                    panic!(
+                        "A patrol_id ({}) should always point to a valid patrol. Patrol count = {}",
+                        patrol_id,
+                        patrols.len()
+                    );
                 };
+                // The finish time is filled in by the patrol
+                patrol.audit.touch(current_time);
 
                 for policeman_id in patrol.policemen_ids.as_slice() {
                     let policeman = policemen.get_mut(*policeman_id).unwrap();
@@ -240,25 +457,188 @@ fn main() {
                     };
                 }
 
-                vehicles.get_mut(patrol.vehicle_id).unwrap().state = VehicleState::Available;
+                let report = reports.get(patrol.report_id).unwrap();
+                let outcome = PatrolOutcome::generate_with_report_type_and_patrol(
+                    &mut generator,
+                    report.report_type,
+                    patrol_id,
+                );
+                involved_persons.extend(outcome.generate_involved_persons(
+                    &mut generator,
+                    &mut citizens,
+                    current_time,
+                ));
+                outcomes.push(outcome);
+
+                let vehicle = vehicles.get_mut(patrol.vehicle_id).unwrap();
+                vehicle.state = VehicleState::Available;
+                // The next patrol of the vehicle sets off from here, which makes up for the
+                // missing return leg
+                vehicle.position = places.get(report.place_id).unwrap().coordinates;
+                events.push(Event {
+                    time: current_time,
+                    action: EventAction::Dispatch,
+                });
+            }
+            EventAction::ShiftChange => {
+                events.push(Event {
+                    time: current_time
+                        .checked_add_signed(Duration::hours(SHIFT_LENGTH_HOURS))
+                        .unwrap(),
+                    action: EventAction::ShiftChange,
+                });
+                events.push(Event {
+                    time: current_time,
+                    action: EventAction::Dispatch,
+                });
+            }
+            EventAction::VehicleService(vehicle_id) | EventAction::VehicleBreakdown(vehicle_id) => {
+                let kind = match event.action {
+                    EventAction::VehicleService(_) => MaintenanceKind::Service,
+                    _ => MaintenanceKind::Breakdown,
+                };
+                let vehicle = vehicles.get_mut(vehicle_id).unwrap();
+                match vehicle.state {
+                    VehicleState::Decommissioned => {}
+                    VehicleState::Available => {
+                        let maintenance = Maintenance::generate_with_kind_and_id(
+                            &mut generator,
+                            vehicle_id,
+                            kind,
+                            current_time,
+                            maintenances.len(),
+                        );
+                        vehicle.state = VehicleState::OutOfService;
+                        events.push(Event {
+                            time: maintenance.end_time,
+                            action: EventAction::VehicleRepaired(maintenance.id),
+                        });
+                        maintenances.push(maintenance);
+                    }
+                    // The vehicle is taken out of service once it is back from a patrol
+                    VehicleState::Occupied | VehicleState::OutOfService => {
+                        events.push(Event {
+                            time: current_time
+                                .checked_add_signed(Duration::minutes(VEHICLE_EVENT_RETRY_MINUTES))
+                                .unwrap(),
+                            action: event.action,
+                        });
+                    }
+                }
+            }
+            EventAction::VehicleRepaired(maintenance_id) => {
+                let maintenance = maintenances.get(maintenance_id).unwrap();
+                let vehicle = vehicles.get_mut(maintenance.vehicle_id).unwrap();
+                vehicle.state = VehicleState::Available;
+                let next_event = match maintenance.kind {
+                    MaintenanceKind::Service => Event {
+                        time: current_time
+                            .checked_add_signed(Duration::days(DAYS_BETWEEN_SERVICES))
+                            .unwrap(),
+                        action: EventAction::VehicleService(vehicle.id),
+                    },
+                    MaintenanceKind::Breakdown => Event {
+                        time: vehicle.generate_next_breakdown_time(&mut generator, current_time),
+                        action: EventAction::VehicleBreakdown(vehicle.id),
+                    },
+                };
+                events.push(next_event);
+                events.push(Event {
+                    time: current_time,
+                    action: EventAction::Dispatch,
+                });
+            }
+            EventAction::VehicleDecommission(vehicle_id) => {
+                let vehicle = vehicles.get_mut(vehicle_id).unwrap();
+                if vehicle.state != VehicleState::Available {
+                    events.push(Event {
+                        time: current_time
+                            .checked_add_signed(Duration::minutes(VEHICLE_EVENT_RETRY_MINUTES))
+                            .unwrap(),
+                        action: event.action,
+                    });
+                    continue;
+                }
+
+                vehicle.state = VehicleState::Decommissioned;
+                vehicle.decommissioning_date = Some(current_time);
+                vehicle.audit.touch(current_time);
+                events.push(Event {
+                    time: generate_procurement_time(&mut generator, current_time),
+                    action: EventAction::VehicleProcurement(vehicle.station_id),
+                });
+            }
+            EventAction::VehicleProcurement(station_id) => {
+                let vehicle = Vehicle::generate_new_with_id_and_station(
+                    &mut generator,
+                    vehicles.len(),
+                    stations.get(station_id).unwrap(),
+                    current_time,
+                );
+                for event in Event::from_vehicle_commissioning(&mut generator, &vehicle) {
+                    events.push(event);
+                }
+                vehicles.push(vehicle);
+                events.push(Event {
+                    time: current_time,
+                    action: EventAction::Dispatch,
+                });
             }
             EventAction::Snapshot(snapshot_name, is_terminal) => {
                 write_places_to_file(snapshot_name, places);
-                write_reports_to_file(snapshot_name, &reports);
+                write_stations_to_file(snapshot_name, stations);
+                write_persons_to_file(snapshot_name, &citizens);
+                write_reports_to_file(snapshot_name, &reports, &citizens, current_time);
                 write_database_policemen_to_file(snapshot_name, &policemen);
                 write_csv_policemen_to_file(snapshot_name, &policemen, current_time);
+                write_hr_policemen_to_file(snapshot_name, &policemen, current_time);
+                let roster_entries = roster.entries_before(&policemen, current_time);
+                write_roster_to_file(snapshot_name, &roster_entries);
+                write_absences_to_file(snapshot_name, roster.absences_before(current_time));
                 write_csv_vehicle_to_file(snapshot_name, &vehicles);
                 write_database_vehicle_to_file(snapshot_name, &vehicles);
+                write_fleet_vehicle_to_file(snapshot_name, &vehicles, current_time);
+                write_maintenances_to_file(snapshot_name, &maintenances, current_time);
                 write_patrols_to_file(snapshot_name, &patrols, current_time);
-                write_policeman_patrol_to_file(snapshot_name, &patrols);
-                dbg!(
+                write_policeman_patrol_to_file(snapshot_name, &patrols, current_time);
+                write_outcomes_to_file(snapshot_name, &outcomes, &patrols, current_time);
+                write_involved_persons_to_file(
                     snapshot_name,
-                    is_terminal,
-                    places.len(),
-                    policemen.len(),
-                    vehicles.len(),
-                    reports.len(),
-                    patrols.len()
+                    &involved_persons,
+                    &patrols,
+                    current_time,
+                );
+                inject_defects_into_files(snapshot_name, DEFECT_RATES, defect_seed);
+                write_spreadsheets_to_file(snapshot_name, STYLE_SPREADSHEET_HEADERS);
+                apply_source_formats_to_files(snapshot_name, SOURCE_FORMATS);
+                let warehouse = Warehouse::build(
+                    current_time,
+                    &calendar,
+                    stations,
+                    places,
+                    &policemen,
+                    &last_name_changes,
+                    &vehicles,
+                    &citizens,
+                    &reports,
+                    &patrols,
+                    &outcomes,
+                );
+                write_warehouse_to_files(snapshot_name, &warehouse);
+                let statistics = Statistics::build(
+                    current_time,
+                    data_start_date,
+                    &policemen,
+                    &roster_entries,
+                    &vehicles,
+                    &reports,
+                    &patrols,
+                );
+                let statistics_paths = write_statistics_to_files(snapshot_name, &statistics);
+                println!(
+                    "{snapshot_name}: {}, see {}",
+                    statistics.summary(),
+                    statistics_paths.join(" and ")
                 );
 
                 if is_terminal {
@@ -266,13 +646,69 @@ fn main() {
                 }
             }
             EventAction::PolicemanLastNameChange => {
-                policemen
-                    .iter_mut()
-                    .map(|policeman| &mut policeman.person)
-                    .choose(&mut generator)
-                    .unwrap()
-                    .change_to_random_surname(&mut generator);
+                let policeman = policemen.iter_mut().choose(&mut generator).unwrap();
+                policeman.audit.touch(current_time);
+                let change = policeman
+                    .person
+                    .change_to_random_surname(&mut generator, current_time);
+                last_name_changes.push(change);
+            }
+        }
+    }
+}
+
+/// Commands working on snapshots which were already generated, e.g. `validate SNAPSHOT_A_`.
+/// The changes found by `diff` are written to the standard output as CSV, with a summary of
+/// each file on the standard error.
+fn run_command(command: &str, arguments: &[String]) {
+    match (command, arguments) {
+        ("validate", [snapshot_name]) => {
+            let violations = validate_snapshot(snapshot_name, SOURCE_FORMATS);
+            violations
+                .iter()
+                .for_each(|violation| println!("{violation}"));
+            if !violations.is_empty() {
+                exit(1);
             }
         }
+        ("diff", [old_snapshot_name, new_snapshot_name]) => {
+            let changes = diff_snapshots(old_snapshot_name, new_snapshot_name, SOURCE_FORMATS);
+            for change in &changes {
+                let column = change.column.map(|column| column.to_string());
+                println!(
+                    "{},{},{},{},{},{}",
+                    change.file_suffix,
+                    change.key,
+                    change.kind.code(),
+                    column.unwrap_or_default(),
+                    change.old_value,
+                    change.new_value
+                );
+            }
+            for file_suffix in changes
+                .iter()
+                .map(|change| change.file_suffix)
+                .collect::<BTreeSet<_>>()
+            {
+                let count = |kind| {
+                    changes
+                        .iter()
+                        .filter(|change| change.file_suffix == file_suffix && change.kind == kind)
+                        .map(|change| &change.key)
+                        .collect::<HashSet<_>>()
+                        .len()
+                };
+                eprintln!(
+                    "{file_suffix}: {} inserted, {} updated, {} deleted",
+                    count(ChangeKind::Inserted),
+                    count(ChangeKind::Updated),
+                    count(ChangeKind::Deleted)
+                );
+            }
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            exit(2);
+        }
     }
 }
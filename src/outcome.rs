@@ -0,0 +1,238 @@
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use rand::prelude::{SliceRandom, ThreadRng};
+use rand::Rng;
+
+use crate::citizen::CitizenRegistry;
+use crate::string_occurrences::parse_column;
+
+const PATROL_OUTCOMES_STRING: &str = include_str!("../data/patrol_outcomes.txt");
+// Mandates are given in multiples of 50 PLN, up to the usual limit of 500 PLN
+const FINE_AMOUNT_STEP: u32 = 50;
+const MAX_FINE_AMOUNT: u32 = 500;
+const MAX_ARRESTED_OFFENDERS: usize = 3;
+const VICTIM_CHANCE: f64 = 0.6;
+const MAX_WITNESSES: usize = 2;
+
+static PATROL_OUTCOME_WEIGHTS: Lazy<Vec<PatrolOutcomeWeight>> =
+    Lazy::new(|| create_patrol_outcome_weights_from_str(PATROL_OUTCOMES_STRING));
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OutcomeKind {
+    NoAction,
+    Warning,
+    Fine,
+    Arrest,
+    Referral,
+    Unfounded,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum InvolvementRole {
+    Offender,
+    Victim,
+    Witness,
+}
+
+/// How a finished patrol ended.
+#[derive(Debug, Copy, Clone)]
+pub struct PatrolOutcome {
+    pub patrol_id: usize,
+    pub kind: OutcomeKind,
+    /// In PLN, only for mandates.
+    pub fine_amount: Option<u32>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct InvolvedPerson {
+    pub patrol_id: usize,
+    pub person_id: usize,
+    pub role: InvolvementRole,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct PatrolOutcomeWeight {
+    report_type: &'static str,
+    kind: OutcomeKind,
+    weight: u32,
+}
+
+pub const ALL_OUTCOME_KINDS: [OutcomeKind; 6] = [
+    OutcomeKind::NoAction,
+    OutcomeKind::Warning,
+    OutcomeKind::Fine,
+    OutcomeKind::Arrest,
+    OutcomeKind::Referral,
+    OutcomeKind::Unfounded,
+];
+
+impl OutcomeKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            OutcomeKind::NoAction => "BRAK DZIALAN",
+            OutcomeKind::Warning => "POUCZENIE",
+            OutcomeKind::Fine => "MANDAT",
+            OutcomeKind::Arrest => "ZATRZYMANIE",
+            OutcomeKind::Referral => "ZAWIADOMIENIE PROKURATURY",
+            OutcomeKind::Unfounded => "BEZPODSTAWNE",
+        }
+    }
+
+    fn from_code(code: &str) -> Option<Self> {
+        ALL_OUTCOME_KINDS
+            .into_iter()
+            .find(|kind| kind.code() == code)
+    }
+}
+
+impl InvolvementRole {
+    pub fn code(&self) -> &'static str {
+        match self {
+            InvolvementRole::Offender => "SPRAWCA",
+            InvolvementRole::Victim => "POSZKODOWANY",
+            InvolvementRole::Witness => "SWIADEK",
+        }
+    }
+}
+
+impl PatrolOutcome {
+    pub fn generate_with_report_type_and_patrol(
+        generator: &mut ThreadRng,
+        report_type: &str,
+        patrol_id: usize,
+    ) -> Self {
+        let Ok(outcome_weight) = PATROL_OUTCOME_WEIGHTS
+            .iter()
+            .filter(|item| item.report_type == report_type)
+            .collect::<Vec<_>>()
+            .choose_weighted(generator, |item| item.weight)
+            .copied()
+        else {
+            panic!(
+                "Every report type should have its outcomes defined in patrol_outcomes.txt. Missing report type \"{}\"",
+                report_type
+            );
+        };
+        let fine_amount = (outcome_weight.kind == OutcomeKind::Fine).then(|| {
+            generator.gen_range(1..=MAX_FINE_AMOUNT / FINE_AMOUNT_STEP) * FINE_AMOUNT_STEP
+        });
+
+        Self {
+            patrol_id,
+            kind: outcome_weight.kind,
+            fine_amount,
+        }
+    }
+
+    /// The offenders, victims and witnesses the patrol dealt with. Offenders may have been
+    /// involved in earlier interventions, while victims and witnesses are always new citizens.
+    pub fn generate_involved_persons(
+        &self,
+        generator: &mut ThreadRng,
+        citizens: &mut CitizenRegistry,
+        time: DateTime<Utc>,
+    ) -> Vec<InvolvedPerson> {
+        let offenders_count = match self.kind {
+            OutcomeKind::NoAction | OutcomeKind::Unfounded => 0,
+            OutcomeKind::Warning | OutcomeKind::Fine => 1,
+            OutcomeKind::Arrest | OutcomeKind::Referral => {
+                generator.gen_range(1..=MAX_ARRESTED_OFFENDERS)
+            }
+        };
+        let victims_count = match self.kind {
+            OutcomeKind::Arrest | OutcomeKind::Referral => {
+                usize::from(generator.gen_bool(VICTIM_CHANCE))
+            }
+            _ => 0,
+        };
+        let witnesses_count = match self.kind {
+            OutcomeKind::Unfounded => 0,
+            _ => generator.gen_range(0..=MAX_WITNESSES),
+        };
+
+        let mut involved_persons = vec![];
+        for _ in 0..offenders_count {
+            let person_id = citizens.choose_offender(generator, time);
+            if involved_persons
+                .iter()
+                .any(|person: &InvolvedPerson| person.person_id == person_id)
+            {
+                continue;
+            }
+            involved_persons.push(InvolvedPerson {
+                patrol_id: self.patrol_id,
+                person_id,
+                role: InvolvementRole::Offender,
+            });
+        }
+        for (role, count) in [
+            (InvolvementRole::Victim, victims_count),
+            (InvolvementRole::Witness, witnesses_count),
+        ] {
+            for _ in 0..count {
+                involved_persons.push(InvolvedPerson {
+                    patrol_id: self.patrol_id,
+                    person_id: citizens.add_citizen(generator, time),
+                    role,
+                });
+            }
+        }
+
+        involved_persons
+    }
+}
+
+fn create_patrol_outcome_weights_from_str(data: &'static str) -> Vec<PatrolOutcomeWeight> {
+    data.split('\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let columns = line.split(',').collect::<Vec<_>>();
+            let [report_type, code, weight] = columns[..] else {
+                panic!(
+                    "Each patrol outcome line should have 3 columns. Found line \"{}\"",
+                    line
+                );
+            };
+            let Some(kind) = OutcomeKind::from_code(code) else {
+                panic!("Unknown patrol outcome \"{}\" in line \"{}\"", code, line);
+            };
+
+            PatrolOutcomeWeight {
+                report_type,
+                kind,
+                weight: parse_column(weight, line),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::thread_rng;
+
+    use crate::outcome::{OutcomeKind, PatrolOutcome};
+    use crate::report::{get_all_report_types, UNFOUNDED_REPORT_TYPE};
+
+    #[test]
+    fn should_generate_fines_only_for_mandates_and_unfounded_outcomes_for_unfounded_reports() {
+        let mut generator = thread_rng();
+        for report_type in get_all_report_types() {
+            for _ in 0..100 {
+                let outcome = PatrolOutcome::generate_with_report_type_and_patrol(
+                    &mut generator,
+                    report_type,
+                    0,
+                );
+
+                assert_eq!(
+                    outcome.fine_amount.is_some(),
+                    outcome.kind == OutcomeKind::Fine
+                );
+                assert_eq!(
+                    outcome.kind == OutcomeKind::Unfounded,
+                    report_type == UNFOUNDED_REPORT_TYPE
+                );
+            }
+        }
+    }
+}
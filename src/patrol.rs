@@ -2,12 +2,23 @@ use chrono::{DateTime, Duration, Utc};
 use rand::prelude::ThreadRng;
 use rand::Rng;
 
-const MIN_SENDING_TO_ARRIVAL_SECONDS: i64 = 1500;
-// 25 minutes
-const MAX_SENDING_TO_ARRIVAL_SECONDS: i64 = 2100;
-// 35 minutes
-const MIN_ARRIVAL_TO_FINISH_SECONDS: i64 = 300; // 5 minutes
-const MAX_ARRIVAL_TO_FINISH_SECONDS: i64 = 1200; // 20 minutes
+use crate::audit::Audit;
+use crate::dispatch::PatrolRequest;
+use crate::place::get_all_places;
+use crate::report::{generate_recording_time, get_report_type_parameters, Report};
+use crate::vehicle::Vehicle;
+
+// Roads are never straight, so the driven distance is longer than the great-circle distance
+const ROAD_DISTANCE_FACTOR: f64 = 1.3;
+// Trips shorter than this are driven at urban speed
+const URBAN_DISTANCE_KM: f64 = 3.0;
+const URBAN_SPEED_KMH: f64 = 40.0;
+const RURAL_SPEED_KMH: f64 = 70.0;
+const SECONDS_PER_HOUR: f64 = 3600.0;
+// 1 minute
+const MIN_DEPARTURE_SECONDS: i64 = 60;
+// 5 minutes
+const MAX_DEPARTURE_SECONDS: i64 = 300;
 
 #[derive(Debug, Clone)]
 pub struct Patrol {
@@ -15,40 +26,74 @@ pub struct Patrol {
     pub report_id: usize,
     pub policemen_ids: Vec<usize>,
     pub vehicle_id: usize,
+    /// The station that sent the patrol, which differs from the report's one in case of mutual aid.
+    pub station_id: usize,
+    /// The road distance driven from the vehicle's position to the reported place. There is no
+    /// return leg, as the vehicle stays in the field afterwards and the next patrol sets off from
+    /// the place of this one, so the outbound legs add up to all the driving.
+    pub distance_km: f64,
+    pub fuel_used_l: f64,
+    /// The vehicle's odometer reading when the patrol was sent.
+    pub odometer_km: f64,
+    /// The moment the patrol was requested and put into the dispatch queue.
+    pub queued_time: DateTime<Utc>,
     pub sending_time: DateTime<Utc>,
     pub arrival_time: DateTime<Utc>,
     pub finish_time: DateTime<Utc>,
+    /// Created when the dispatcher enters the patrol, updated as the arrival and finish times are
+    /// filled in.
+    pub audit: Audit,
 }
 
 impl Patrol {
-    pub fn generate_with_report_id_policemen_vehicles_and_sending_time_and_id(
+    /// The patrol is never recorded before its report, which may be entered late.
+    pub fn generate_with_request_policemen_vehicle_and_sending_time_and_id(
         generator: &mut ThreadRng,
-        report_id: usize,
+        request: &PatrolRequest,
+        report: &Report,
         policemen_ids: Vec<usize>,
-        vehicle_id: usize,
+        vehicle: &Vehicle,
         sending_time: DateTime<Utc>,
         id: usize,
     ) -> Self {
+        let place = get_all_places().get(request.place_id).unwrap();
+        let distance_km = vehicle.position.distance_km(&place.coordinates) * ROAD_DISTANCE_FACTOR;
+        let is_urban = distance_km < URBAN_DISTANCE_KM;
+        let speed_kmh = if is_urban {
+            URBAN_SPEED_KMH
+        } else {
+            RURAL_SPEED_KMH
+        };
+        let driving_seconds = (distance_km / speed_kmh * SECONDS_PER_HOUR) as i64;
         let arriving_time =
-            generator.gen_range(MIN_SENDING_TO_ARRIVAL_SECONDS..MAX_SENDING_TO_ARRIVAL_SECONDS);
+            generator.gen_range(MIN_DEPARTURE_SECONDS..MAX_DEPARTURE_SECONDS) + driving_seconds;
         let arrival_time = sending_time
             .checked_add_signed(Duration::seconds(arriving_time))
             .unwrap();
 
         let processing_time =
-            generator.gen_range(MIN_ARRIVAL_TO_FINISH_SECONDS..MAX_ARRIVAL_TO_FINISH_SECONDS);
+            get_report_type_parameters(report.reported_type).generate_on_scene_seconds(generator);
         let finish_time = arrival_time
             .checked_add_signed(Duration::seconds(processing_time))
             .unwrap();
 
+        let recorded_at =
+            generate_recording_time(generator, sending_time).max(report.audit.created_at);
+
         Self {
             id,
-            report_id,
+            report_id: request.report_id,
             policemen_ids,
-            vehicle_id,
+            vehicle_id: vehicle.id,
+            station_id: vehicle.station_id,
+            distance_km,
+            fuel_used_l: vehicle.model.fuel_used_l(distance_km, is_urban),
+            odometer_km: vehicle.odometer_km,
+            queued_time: request.requested_time,
             sending_time,
             arrival_time,
             finish_time,
+            audit: Audit::generate_with_time(generator, recorded_at),
         }
     }
 }
@@ -1,8 +1,9 @@
-use chrono::{DateTime, Datelike, Days, TimeZone, Timelike, Utc};
+use chrono::{DateTime, Datelike, Days, Duration, TimeZone, Timelike, Utc};
 use once_cell::sync::Lazy;
 use rand::rngs::ThreadRng;
 use rand::Rng;
 
+use crate::audit::Audit;
 use crate::string_occurrences::StringOccurrences;
 
 const FIRST_NAMES_STRING: &str = include_str!("../data/first_names.txt");
@@ -14,6 +15,8 @@ const PESEL_SUFFIX_MAX: u64 = 99999;
 const PESEL_SUFFIX_MIN: u64 = 10000;
 const SERVICE_NUMBER_MIN: u32 = 100000;
 const SERVICE_NUMBER_MAX: u32 = 999999;
+const MIN_CITIZEN_AGE_DAYS: i64 = 6575; // 18 years
+const MAX_CITIZEN_AGE_DAYS: i64 = 32872; // 90 years
 const MIN_DAYS_AFTER_BIRTH_TO_EMPLOYMENT: u64 = 7670; // 21 years
 const MAX_DAYS_AFTER_BIRTH_TO_EMPLOYMENT: u64 = 12783; // 35 years
 const MIN_EMPLOYMENT_DURATION: u64 = 30; // 1 month
@@ -46,6 +49,14 @@ pub struct Person {
     pub pesel_number: u64,
 }
 
+#[derive(Debug, Copy, Clone)]
+pub struct LastNameChange {
+    pub person_id: usize,
+    pub time: DateTime<Utc>,
+    pub previous_last_name: &'static str,
+    pub new_last_name: &'static str,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum PolicemanState {
     Available,
@@ -56,11 +67,13 @@ pub enum PolicemanState {
 #[derive(Debug, Copy, Clone)]
 pub struct Policeman {
     pub person: Person,
+    pub station_id: usize,
     pub state: PolicemanState,
     pub service_number: u32,
     pub rank: &'static str,
     pub employment_date: DateTime<Utc>,
     pub resignment_date: DateTime<Utc>,
+    pub audit: Audit,
 }
 
 impl Person {
@@ -77,38 +90,54 @@ impl Person {
         }
     }
 
-    pub fn change_to_random_surname(&mut self, generator: &mut ThreadRng) {
+    /// An adult living in the area at the given time.
+    pub fn generate_citizen_with_id(
+        generator: &mut ThreadRng,
+        time: DateTime<Utc>,
+        id: usize,
+    ) -> Self {
+        let mut person = Self::generate_with_id(generator, id);
+        let age_days = generator.gen_range(MIN_CITIZEN_AGE_DAYS..MAX_CITIZEN_AGE_DAYS);
+        person.birth_date = time
+            .checked_sub_signed(Duration::days(age_days))
+            .unwrap()
+            .with_hour(0)
+            .unwrap()
+            .with_minute(0)
+            .unwrap()
+            .with_second(0)
+            .unwrap();
+        person.pesel_number = birth_date_to_pesel(generator, &person.birth_date);
+        person
+    }
+
+    pub fn change_to_random_surname(
+        &mut self,
+        generator: &mut ThreadRng,
+        time: DateTime<Utc>,
+    ) -> LastNameChange {
         let new_name = LAST_NAMES_ENTRIES.get_random_entry(generator);
         println!(
             "Changed last name of person {}: {} -> {}",
             self.pesel_number, self.last_name, new_name
         );
+        let change = LastNameChange {
+            person_id: self.id,
+            time,
+            previous_last_name: self.last_name,
+            new_last_name: new_name,
+        };
         self.last_name = new_name;
+        change
     }
 }
 
 impl Policeman {
-    pub fn generate_with_id(generator: &mut ThreadRng, id: usize) -> Self {
-        let person = Person::generate_with_id(generator, id);
-        let employment_date =
-            generate_employment_date_from_birth_date(generator, &person.birth_date);
-        let resignment_date =
-            generate_resignation_date_from_employment_date(generator, &employment_date);
-
-        Self {
-            service_number: generator.gen_range(SERVICE_NUMBER_MIN..SERVICE_NUMBER_MAX),
-            person,
-            state: PolicemanState::Available,
-            rank: RANK_ENTRIES.get_random_entry(generator),
-            employment_date,
-            resignment_date,
-        }
-    }
-
-    pub fn generate_just_employed_with_id(
+    pub fn generate_just_employed_with_id_and_station(
         generator: &mut ThreadRng,
         employment_date: &DateTime<Utc>,
         id: usize,
+        station_id: usize,
     ) -> Self {
         let mut person = Person::generate_with_id(generator, id);
         person.birth_date = generate_birth_date_from_employment_date(generator, employment_date);
@@ -118,14 +147,20 @@ impl Policeman {
         Self {
             service_number: generator.gen_range(SERVICE_NUMBER_MIN..SERVICE_NUMBER_MAX),
             person,
+            station_id,
             state: PolicemanState::Available,
             rank: RANK_ENTRIES.get_random_entry(generator),
             employment_date: *employment_date,
             resignment_date,
+            audit: Audit::generate_with_time(generator, *employment_date),
         }
     }
 }
 
+pub fn generate_last_name(generator: &mut ThreadRng) -> &'static str {
+    LAST_NAMES_ENTRIES.get_random_entry(generator)
+}
+
 fn generate_birth_date(generator: &mut ThreadRng) -> DateTime<Utc> {
     let min_birth_date = *Lazy::force(&MIN_BIRTH_DATE);
     let max_birth_date = *Lazy::force(&MAX_BIRTH_DATE);
@@ -151,17 +186,6 @@ fn generate_birth_date_from_employment_date(
         .unwrap()
 }
 
-fn generate_employment_date_from_birth_date(
-    generator: &mut ThreadRng,
-    birth_date: &DateTime<Utc>,
-) -> DateTime<Utc> {
-    let days_after_birth =
-        generator.gen_range(MIN_DAYS_AFTER_BIRTH_TO_EMPLOYMENT..MAX_DAYS_AFTER_BIRTH_TO_EMPLOYMENT);
-    birth_date
-        .checked_add_days(Days::new(days_after_birth))
-        .unwrap()
-}
-
 fn generate_resignation_date_from_employment_date(
     generator: &mut ThreadRng,
     employment_date: &DateTime<Utc>,
@@ -180,12 +204,12 @@ fn birth_date_to_pesel(generator: &mut ThreadRng, birth_date: &DateTime<Utc>) ->
     output += birth_date.day() as u64;
     output *= 100000;
     output += generator.gen_range(PESEL_SUFFIX_MIN..PESEL_SUFFIX_MAX);
-    return output;
+    output
 }
 
 #[cfg(test)]
 mod tests {
-    use chrono::Utc;
+    use chrono::{TimeZone, Utc};
     use rand::thread_rng;
 
     use crate::person::Policeman;
@@ -193,9 +217,14 @@ mod tests {
     #[test]
     fn should_generate_policeman() {
         let mut generator = thread_rng();
-        let current_date = Utc::now();
+        let employment_date = Utc.with_ymd_and_hms(2015, 6, 1, 0, 0, 0).unwrap();
         for _ in 0..100 {
-            let a = Policeman::generate_with_id(&mut generator, 0);
+            let a = Policeman::generate_just_employed_with_id_and_station(
+                &mut generator,
+                &employment_date,
+                0,
+                0,
+            );
             dbg!(a);
         }
     }
@@ -1,39 +1,146 @@
 use once_cell::sync::Lazy;
+use std::str::FromStr;
 
 const PLACES_STRING: &str = include_str!("../data/places.txt");
+const CITIES_STRING: &str = include_str!("../data/cities.txt");
+const EARTH_RADIUS_KM: f64 = 6371.0;
+const KM_PER_LATITUDE_DEGREE: f64 = 111.32;
 
+static CITIES: Lazy<Vec<City>> = Lazy::new(|| create_cities_from_str(CITIES_STRING));
 static PLACES: Lazy<Vec<Place>> = Lazy::new(|| create_places_from_str(PLACES_STRING));
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct City {
+    pub name: &'static str,
+    pub center: Coordinates,
+    pub radius_km: f64,
+    pub county: &'static str,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct Place {
     pub id: usize,
     pub city: &'static str,
     pub street: &'static str,
+    pub coordinates: Coordinates,
+}
+
+impl Coordinates {
+    /// The great-circle distance computed with the haversine formula.
+    pub fn distance_km(&self, other: &Coordinates) -> f64 {
+        let latitude_delta = (other.latitude - self.latitude).to_radians();
+        let longitude_delta = (other.longitude - self.longitude).to_radians();
+        let a = (latitude_delta / 2.0).sin().powi(2)
+            + self.latitude.to_radians().cos()
+                * other.latitude.to_radians().cos()
+                * (longitude_delta / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+    }
 }
 
 pub fn get_all_places() -> &'static Vec<Place> {
     Lazy::force(&PLACES)
 }
 
+pub fn get_city(name: &str) -> &'static City {
+    let Some(city) = CITIES.iter().find(|city| city.name == name) else {
+        panic!(
+            "Every city should be defined in cities.txt. Missing city \"{}\"",
+            name
+        );
+    };
+    city
+}
+
+fn create_cities_from_str(data: &'static str) -> Vec<City> {
+    data.split('\n')
+        .filter_map(|line| {
+            let mut line_split = line.split(',');
+            let name = line_split.next()?;
+            let latitude = f64::from_str(line_split.next()?).ok()?;
+            let longitude = f64::from_str(line_split.next()?).ok()?;
+            let radius_km = f64::from_str(line_split.next()?).ok()?;
+            let county = line_split.next()?;
+
+            Some(City {
+                name,
+                center: Coordinates {
+                    latitude,
+                    longitude,
+                },
+                radius_km,
+                county,
+            })
+        })
+        .collect()
+}
+
+/// Places may define their own coordinates in the third and fourth column. Otherwise they are
+/// placed deterministically within their city, based on the street name.
 fn create_places_from_str(data: &'static str) -> Vec<Place> {
     let mut counter = 0;
     data.split('\n')
         .filter_map(|line| {
             let mut line_split = line.split(',');
-            let Some(city) = line_split.next() else {
-                return None;
-            };
-            let Some(street) = line_split.next() else {
-                return None;
+            let city = line_split.next()?;
+            let street = line_split.next()?;
+            let latitude = line_split.next().and_then(|item| f64::from_str(item).ok());
+            let longitude = line_split.next().and_then(|item| f64::from_str(item).ok());
+            let coordinates = match (latitude, longitude) {
+                (Some(latitude), Some(longitude)) => Coordinates {
+                    latitude,
+                    longitude,
+                },
+                _ => synthetic_street_coordinates(get_city(city), street),
             };
 
             let place = Some(Place {
                 id: counter,
                 city,
                 street,
+                coordinates,
             });
             counter += 1;
             place
         })
         .collect()
 }
+
+fn synthetic_street_coordinates(city: &City, street: &str) -> Coordinates {
+    let hash = fnv1a_hash(street);
+    let angle = (hash & 0xFFFF) as f64 / 65536.0 * std::f64::consts::TAU;
+    // Square root keeps the streets uniformly spread over the city's area
+    let distance_km = (((hash >> 16) & 0xFFFF) as f64 / 65536.0).sqrt() * city.radius_km;
+    let km_per_longitude_degree = KM_PER_LATITUDE_DEGREE * city.center.latitude.to_radians().cos();
+
+    Coordinates {
+        latitude: city.center.latitude + distance_km * angle.sin() / KM_PER_LATITUDE_DEGREE,
+        longitude: city.center.longitude + distance_km * angle.cos() / km_per_longitude_degree,
+    }
+}
+
+/// A stable hash, so that the synthetic coordinates do not change between runs and releases.
+fn fnv1a_hash(value: &str) -> u64 {
+    value.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::place::{get_all_places, get_city};
+
+    #[test]
+    fn should_place_streets_within_their_city() {
+        for place in get_all_places() {
+            let city = get_city(place.city);
+            assert!(place.coordinates.distance_km(&city.center) <= city.radius_km + 0.01);
+        }
+    }
+}
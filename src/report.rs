@@ -1,42 +1,434 @@
-use crate::person::Person;
-use crate::string_occurrences::StringOccurrences;
-use chrono::{DateTime, Utc};
+use crate::audit::Audit;
+use crate::person::{generate_last_name, Person};
+use crate::place::get_all_places;
+use crate::station::get_responsible_station;
+use crate::string_occurrences::{parse_column, StringOccurrences};
+use chrono::{DateTime, Duration, Utc};
 use once_cell::sync::Lazy;
 use rand::prelude::ThreadRng;
 use rand::Rng;
 
 const REPORT_TYPES_STRING: &str = include_str!("../data/report_types.txt");
+const REPORT_TYPE_PARAMETERS_STRING: &str = include_str!("../data/report_type_parameters.txt");
+pub const UNFOUNDED_REPORT_TYPE: &str = "BEZPODSTAWNE";
+// Some records are entered into the source systems days after the event they describe
+const LATE_RECORD_CHANCE: f64 = 0.03;
+const MIN_LATE_RECORD_DELAY_HOURS: i64 = 12;
+const MAX_LATE_RECORD_DELAY_HOURS: i64 = 240;
+// Some reports are entered with a mistake, which an operator corrects later
+const DATA_ENTRY_ERROR_CHANCE: f64 = 0.02;
+const MIN_CORRECTION_DELAY_HOURS: i64 = 1;
+const MAX_CORRECTION_DELAY_HOURS: i64 = 720;
+// Some reports are entered twice by mistake, and the duplicate is deleted later
+const ERRONEOUS_DUPLICATE_CHANCE: f64 = 0.01;
+const HARD_DELETION_CHANCE: f64 = 0.3;
+const MIN_DELETION_DELAY_HOURS: i64 = 1;
+const MAX_DELETION_DELAY_HOURS: i64 = 72;
 
 static REPORT_TYPE_ENTRIES: Lazy<StringOccurrences> =
     Lazy::new(|| StringOccurrences::from_str(REPORT_TYPES_STRING));
+static REPORT_TYPE_PARAMETERS: Lazy<Vec<ReportTypeParameters>> =
+    Lazy::new(|| create_report_type_parameters_from_str(REPORT_TYPE_PARAMETERS_STRING));
+
+#[derive(Debug, Copy, Clone)]
+pub struct ReportTypeParameters {
+    pub report_type: &'static str,
+    /// Lower values are dispatched first.
+    pub priority: u32,
+    pub min_patrols: u32,
+    pub max_patrols: u32,
+    pub policemen_per_patrol: usize,
+    pub min_dispatch_delay_seconds: i64,
+    pub max_dispatch_delay_seconds: i64,
+    pub median_on_scene_seconds: f64,
+    pub on_scene_sigma: f64,
+    pub unfounded_chance: f64,
+}
+
+/// A wrong value entered into the source system instead of the true one.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DataEntryError {
+    ReportType(&'static str),
+    Place(usize),
+    ReporterLastName(&'static str),
+}
 
 #[derive(Debug, Copy, Clone)]
 pub struct Report {
     pub id: usize,
+    /// The type assigned by the dispatcher when the report was received.
+    pub reported_type: &'static str,
+    /// The final classification, which is BEZPODSTAWNE if the report turned out to be unfounded.
     pub report_type: &'static str,
     pub time: DateTime<Utc>,
-    pub reporter: Person,
+    /// The citizen who made the report.
+    pub reporter_id: usize,
     pub place_id: usize,
+    /// The station responsible for the reported place.
+    pub station_id: usize,
+    /// A mistake visible in the source system until it is corrected.
+    pub data_entry_error: Option<DataEntryError>,
+    /// Created when the report is entered into the source system.
+    pub audit: Audit,
+    pub is_deleted: bool,
+    /// Hard deleted reports disappear from the source system.
+    pub is_hard_deleted: bool,
+}
+
+/// The types a report can be given when it is received.
+pub fn get_reported_types() -> impl Iterator<Item = &'static str> {
+    REPORT_TYPE_ENTRIES.entries()
+}
+
+/// The reported types and BEZPODSTAWNE, which a report can only turn out to be.
+pub fn get_all_report_types() -> impl Iterator<Item = &'static str> {
+    get_reported_types().chain([UNFOUNDED_REPORT_TYPE])
 }
 
 impl Report {
-    pub fn generate_with_time_and_id(
+    pub fn generate_with_time_reporter_and_id(
         generator: &mut ThreadRng,
         time: DateTime<Utc>,
         max_place_id: usize,
+        reporter: &Person,
         id: usize,
     ) -> Self {
-        // The ID related to the reporter does not need to be unique
-        const PERSON_ID: usize = 0;
-
         let place_id = generator.gen_range(0..max_place_id);
+        let station_id = get_responsible_station(get_all_places().get(place_id).unwrap()).id;
+        let reported_type = REPORT_TYPE_ENTRIES.get_random_entry(generator);
+        let parameters = get_report_type_parameters(reported_type);
+        let report_type = if generator.gen_bool(parameters.unfounded_chance) {
+            UNFOUNDED_REPORT_TYPE
+        } else {
+            reported_type
+        };
+
+        let data_entry_error = generator
+            .gen_bool(DATA_ENTRY_ERROR_CHANCE)
+            .then(|| match generator.gen_range(0..3) {
+                0 => DataEntryError::ReportType(generate_different(
+                    generator,
+                    report_type,
+                    |generator| REPORT_TYPE_ENTRIES.get_random_entry(generator),
+                )),
+                1 => DataEntryError::Place(generate_different(generator, place_id, |generator| {
+                    generator.gen_range(0..max_place_id)
+                })),
+                _ => DataEntryError::ReporterLastName(generate_different(
+                    generator,
+                    reporter.last_name,
+                    generate_last_name,
+                )),
+            });
+        let recorded_at = generate_recording_time(generator, time);
 
         Self {
             id,
-            report_type: REPORT_TYPE_ENTRIES.get_random_entry(generator),
+            reported_type,
+            report_type,
             time,
-            reporter: Person::generate_with_id(generator, PERSON_ID),
+            reporter_id: reporter.id,
             place_id,
+            station_id,
+            data_entry_error,
+            audit: Audit::generate_with_time(generator, recorded_at),
+            is_deleted: false,
+            is_hard_deleted: false,
+        }
+    }
+
+    /// A copy of the report entered by mistake, which gets deleted later.
+    pub fn generate_erroneous_duplicate_with_id(
+        &self,
+        generator: &mut ThreadRng,
+        id: usize,
+    ) -> Option<Self> {
+        if !generator.gen_bool(ERRONEOUS_DUPLICATE_CHANCE) {
+            return None;
+        }
+
+        Some(Self {
+            id,
+            data_entry_error: None,
+            ..*self
+        })
+    }
+
+    pub fn generate_correction_time(&self, generator: &mut ThreadRng) -> Option<DateTime<Utc>> {
+        self.data_entry_error?;
+        let delay_hours =
+            generator.gen_range(MIN_CORRECTION_DELAY_HOURS..=MAX_CORRECTION_DELAY_HOURS);
+        self.audit
+            .created_at
+            .checked_add_signed(Duration::hours(delay_hours))
+    }
+
+    pub fn generate_deletion_time(&self, generator: &mut ThreadRng) -> DateTime<Utc> {
+        let delay_hours = generator.gen_range(MIN_DELETION_DELAY_HOURS..=MAX_DELETION_DELAY_HOURS);
+        self.audit
+            .created_at
+            .checked_add_signed(Duration::hours(delay_hours))
+            .unwrap()
+    }
+
+    pub fn correct(&mut self, time: DateTime<Utc>) {
+        self.data_entry_error = None;
+        self.audit.touch(time);
+    }
+
+    pub fn delete(&mut self, generator: &mut ThreadRng, time: DateTime<Utc>) {
+        self.is_deleted = true;
+        self.is_hard_deleted = generator.gen_bool(HARD_DELETION_CHANCE);
+        self.audit.touch(time);
+    }
+
+    /// The type as currently stored in the source system.
+    pub fn recorded_report_type(&self) -> &'static str {
+        match self.data_entry_error {
+            Some(DataEntryError::ReportType(report_type)) => report_type,
+            _ => self.report_type,
+        }
+    }
+
+    /// The place as currently stored in the source system.
+    pub fn recorded_place_id(&self) -> usize {
+        match self.data_entry_error {
+            Some(DataEntryError::Place(place_id)) => place_id,
+            _ => self.place_id,
+        }
+    }
+
+    /// The reporter's last name as currently stored in the source system.
+    pub fn recorded_reporter_last_name(&self, reporter: &Person) -> &'static str {
+        match self.data_entry_error {
+            Some(DataEntryError::ReporterLastName(last_name)) => last_name,
+            _ => reporter.last_name,
+        }
+    }
+}
+
+impl ReportTypeParameters {
+    pub fn generate_patrols_count(&self, generator: &mut ThreadRng) -> u32 {
+        generator.gen_range(self.min_patrols..=self.max_patrols)
+    }
+
+    pub fn generate_dispatch_delay_seconds(&self, generator: &mut ThreadRng) -> i64 {
+        generator.gen_range(self.min_dispatch_delay_seconds..=self.max_dispatch_delay_seconds)
+    }
+
+    /// On-scene durations follow a log-normal distribution around the configured median.
+    pub fn generate_on_scene_seconds(&self, generator: &mut ThreadRng) -> i64 {
+        // Box-Muller transform
+        let first_uniform: f64 = 1.0 - generator.gen::<f64>();
+        let second_uniform: f64 = generator.gen();
+        let standard_normal = (-2.0 * first_uniform.ln()).sqrt()
+            * (2.0 * std::f64::consts::PI * second_uniform).cos();
+        (self.median_on_scene_seconds * (self.on_scene_sigma * standard_normal).exp()) as i64
+    }
+}
+
+/// Draws values until one differs from the true one.
+fn generate_different<T: PartialEq>(
+    generator: &mut ThreadRng,
+    true_value: T,
+    generate: impl Fn(&mut ThreadRng) -> T,
+) -> T {
+    loop {
+        let value = generate(generator);
+        if value != true_value {
+            return value;
+        }
+    }
+}
+
+/// Most records are entered right away, the late ones only after a delay.
+pub fn generate_recording_time(generator: &mut ThreadRng, time: DateTime<Utc>) -> DateTime<Utc> {
+    if !generator.gen_bool(LATE_RECORD_CHANCE) {
+        return time;
+    }
+
+    let delay_hours =
+        generator.gen_range(MIN_LATE_RECORD_DELAY_HOURS..=MAX_LATE_RECORD_DELAY_HOURS);
+    time.checked_add_signed(Duration::hours(delay_hours))
+        .unwrap()
+}
+
+pub fn get_report_type_parameters(report_type: &str) -> &'static ReportTypeParameters {
+    let Some(parameters) = REPORT_TYPE_PARAMETERS
+        .iter()
+        .find(|parameters| parameters.report_type == report_type)
+    else {
+        panic!(
+            "Every report type should have its parameters defined. Missing parameters for \"{}\"",
+            report_type
+        );
+    };
+    parameters
+}
+
+/// Each line contains: report type, dispatch priority, min and max patrols count, policemen per patrol, min and max
+/// seconds from the report to sending the patrols, median on-scene seconds, on-scene log-normal
+/// sigma and the chance of the report turning out unfounded.
+fn create_report_type_parameters_from_str(data: &'static str) -> Vec<ReportTypeParameters> {
+    data.split('\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let columns = line.split(',').collect::<Vec<_>>();
+            let [report_type, priority, min_patrols, max_patrols, policemen_per_patrol, min_dispatch_delay_seconds, max_dispatch_delay_seconds, median_on_scene_seconds, on_scene_sigma, unfounded_chance] =
+                columns[..]
+            else {
+                panic!("Each report type parameters line should have 10 columns. Found line \"{}\"", line);
+            };
+
+            ReportTypeParameters {
+                report_type,
+                priority: parse_column(priority, line),
+                min_patrols: parse_column(min_patrols, line),
+                max_patrols: parse_column(max_patrols, line),
+                policemen_per_patrol: parse_column(policemen_per_patrol, line),
+                min_dispatch_delay_seconds: parse_column(min_dispatch_delay_seconds, line),
+                max_dispatch_delay_seconds: parse_column(max_dispatch_delay_seconds, line),
+                median_on_scene_seconds: parse_column(median_on_scene_seconds, line),
+                on_scene_sigma: parse_column(on_scene_sigma, line),
+                unfounded_chance: parse_column(unfounded_chance, line),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use rand::thread_rng;
+
+    use crate::person::Person;
+    use crate::report::{
+        get_reported_types, DataEntryError, Report, REPORT_TYPE_PARAMETERS, UNFOUNDED_REPORT_TYPE,
+    };
+
+    #[test]
+    fn should_show_the_wrong_values_until_corrected() {
+        let mut generator = thread_rng();
+        let time = Utc.with_ymd_and_hms(2023, 6, 5, 12, 0, 0).unwrap();
+        let reporter = Person::generate_with_id(&mut generator, 0);
+        let mut report =
+            Report::generate_with_time_reporter_and_id(&mut generator, time, 10, &reporter, 0);
+        let wrong_place_id = (report.place_id + 1) % 10;
+        let wrong_report_type = if report.report_type == "PILNE" {
+            "KRYTYCZNE"
+        } else {
+            "PILNE"
+        };
+        let wrong_last_name = if reporter.last_name == "NOWAK" {
+            "KOWALSKI"
+        } else {
+            "NOWAK"
+        };
+
+        let assert_corrected = |report: &Report| {
+            assert_eq!(report.recorded_place_id(), report.place_id);
+            assert_eq!(report.recorded_report_type(), report.report_type);
+            assert_eq!(
+                report.recorded_reporter_last_name(&reporter),
+                reporter.last_name
+            );
+        };
+
+        report.data_entry_error = Some(DataEntryError::Place(wrong_place_id));
+        assert_eq!(report.recorded_place_id(), wrong_place_id);
+        report.correct(time);
+        assert_corrected(&report);
+
+        report.data_entry_error = Some(DataEntryError::ReportType(wrong_report_type));
+        assert_eq!(report.recorded_report_type(), wrong_report_type);
+        report.correct(time);
+        assert_corrected(&report);
+
+        report.data_entry_error = Some(DataEntryError::ReporterLastName(wrong_last_name));
+        assert_eq!(
+            report.recorded_reporter_last_name(&reporter),
+            wrong_last_name
+        );
+        report.correct(time);
+        assert_corrected(&report);
+    }
+
+    #[test]
+    fn should_generate_data_entry_errors_differing_from_the_true_values() {
+        let mut generator = thread_rng();
+        let time = Utc.with_ymd_and_hms(2023, 6, 5, 12, 0, 0).unwrap();
+        let reporter = Person::generate_with_id(&mut generator, 0);
+
+        for id in 0..20000 {
+            // Only two places, so that the true one would often be drawn again
+            let report =
+                Report::generate_with_time_reporter_and_id(&mut generator, time, 2, &reporter, id);
+            match report.data_entry_error {
+                Some(DataEntryError::ReportType(report_type)) => {
+                    assert_ne!(report_type, report.report_type)
+                }
+                Some(DataEntryError::Place(place_id)) => assert_ne!(place_id, report.place_id),
+                Some(DataEntryError::ReporterLastName(last_name)) => {
+                    assert_ne!(last_name, reporter.last_name)
+                }
+                None => {}
+            }
+        }
+    }
+
+    #[test]
+    fn should_update_the_audit_on_correction_and_deletion() {
+        let mut generator = thread_rng();
+        let time = Utc.with_ymd_and_hms(2023, 6, 5, 12, 0, 0).unwrap();
+        let reporter = Person::generate_with_id(&mut generator, 0);
+        let mut report =
+            Report::generate_with_time_reporter_and_id(&mut generator, time, 10, &reporter, 0);
+        let created_at = report.audit.created_at;
+
+        report.correct(created_at + Duration::hours(2));
+        assert_eq!(report.audit.updated_at, created_at + Duration::hours(2));
+        report.delete(&mut generator, created_at + Duration::hours(5));
+        assert_eq!(report.audit.updated_at, created_at + Duration::hours(5));
+        assert_eq!(report.audit.created_at, created_at);
+    }
+
+    #[test]
+    fn should_delete_some_reports_softly_and_others_hard() {
+        let mut generator = thread_rng();
+        let time = Utc.with_ymd_and_hms(2023, 6, 5, 12, 0, 0).unwrap();
+        let reporter = Person::generate_with_id(&mut generator, 0);
+        let reports = (0..200)
+            .map(|id| {
+                let mut report = Report::generate_with_time_reporter_and_id(
+                    &mut generator,
+                    time,
+                    10,
+                    &reporter,
+                    id,
+                );
+                report.delete(&mut generator, time + Duration::hours(1));
+                report
+            })
+            .collect::<Vec<_>>();
+
+        assert!(reports.iter().all(|report| report.is_deleted));
+        assert!(reports.iter().any(|report| report.is_hard_deleted));
+        assert!(reports.iter().any(|report| !report.is_hard_deleted));
+    }
+
+    #[test]
+    fn should_have_consistent_parameters_for_every_reported_type() {
+        assert!(get_reported_types().all(|report_type| report_type != UNFOUNDED_REPORT_TYPE));
+        for report_type in get_reported_types() {
+            let parameters = REPORT_TYPE_PARAMETERS
+                .iter()
+                .find(|parameters| parameters.report_type == report_type);
+            let Some(parameters) = parameters else {
+                panic!("Missing parameters for \"{report_type}\"");
+            };
+
+            assert!(parameters.min_patrols <= parameters.max_patrols);
+            assert!(parameters.min_dispatch_delay_seconds <= parameters.max_dispatch_delay_seconds);
         }
     }
 }
@@ -0,0 +1,311 @@
+use chrono::{DateTime, Datelike, Days, Duration, NaiveDate, NaiveTime, Timelike, Utc};
+use rand::prelude::ThreadRng;
+use rand::Rng;
+
+use crate::calendar::{DAY_SHIFT_START_HOUR, NIGHT_SHIFT_START_HOUR};
+use crate::person::Policeman;
+
+pub const SHIFT_LENGTH_HOURS: i64 = NIGHT_SHIFT_START_HOUR as i64 - DAY_SHIFT_START_HOUR as i64;
+// The 12h rotation: a day shift, a night shift and two days off
+const ROTATION_LENGTH_DAYS: i64 = 4;
+const DAY_SHIFT_ROTATION_DAY: i64 = 0;
+const NIGHT_SHIFT_ROTATION_DAY: i64 = 1;
+// Annual leave of 26 working days, taken in blocks of calendar days
+const ANNUAL_LEAVE_BLOCKS_DAYS: [u64; 3] = [14, 7, 5];
+// About 1.5 sick leaves per year
+const SICK_LEAVE_DAILY_CHANCE: f64 = 1.5 / 365.0;
+const MIN_SICK_LEAVE_DAYS: u64 = 3;
+const MAX_SICK_LEAVE_DAYS: u64 = 14;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AbsenceKind {
+    AnnualLeave,
+    SickLeave,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Absence {
+    pub policeman_id: usize,
+    pub kind: AbsenceKind,
+    pub start_date: NaiveDate,
+    /// Inclusive.
+    pub end_date: NaiveDate,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Shift {
+    Day,
+    Night,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct RosterEntry {
+    pub policeman_id: usize,
+    pub date: NaiveDate,
+    pub shift: Shift,
+    pub absence: Option<AbsenceKind>,
+}
+
+#[derive(Debug, Clone)]
+struct PolicemanRoster {
+    rotation_offset: i64,
+    absences: Vec<Absence>,
+}
+
+/// The shift schedule and absences of every policeman, indexed by the policeman's id.
+#[derive(Debug, Clone, Default)]
+pub struct Roster {
+    policemen: Vec<PolicemanRoster>,
+}
+
+impl AbsenceKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AbsenceKind::AnnualLeave => "URLOP",
+            AbsenceKind::SickLeave => "ZWOLNIENIE LEKARSKIE",
+        }
+    }
+}
+
+impl Shift {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Shift::Day => "DZIENNA",
+            Shift::Night => "NOCNA",
+        }
+    }
+}
+
+impl RosterEntry {
+    /// The part of the shift worked before the given time, none if the policeman was absent.
+    pub fn on_duty_seconds_before(&self, time: DateTime<Utc>) -> i64 {
+        if self.absence.is_some() {
+            return 0;
+        }
+        let start = shift_start(self.date, self.shift);
+        let end = start + Duration::hours(SHIFT_LENGTH_HOURS);
+        (end.min(time) - start).num_seconds().max(0)
+    }
+}
+
+impl Roster {
+    /// Assigns the policeman to a rotation and plans the absences until the end of the simulation.
+    pub fn add_policeman(
+        &mut self,
+        generator: &mut ThreadRng,
+        policeman: &Policeman,
+        data_end_date: DateTime<Utc>,
+    ) {
+        assert_eq!(policeman.person.id, self.policemen.len());
+        let start_date = policeman.employment_date.date_naive();
+        let end_date = policeman.resignment_date.min(data_end_date).date_naive();
+        let mut absences = vec![];
+
+        for year in start_date.year()..=end_date.year() {
+            for block_days in ANNUAL_LEAVE_BLOCKS_DAYS {
+                let first_day = NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+                let start = first_day
+                    .checked_add_days(Days::new(generator.gen_range(0..365 - block_days)))
+                    .unwrap();
+                let end = start.checked_add_days(Days::new(block_days - 1)).unwrap();
+                if start < start_date || end > end_date || overlaps(&absences, start, end) {
+                    continue;
+                }
+                absences.push(Absence {
+                    policeman_id: policeman.person.id,
+                    kind: AbsenceKind::AnnualLeave,
+                    start_date: start,
+                    end_date: end,
+                });
+            }
+        }
+
+        let mut date = start_date;
+        while date <= end_date {
+            if generator.gen_bool(SICK_LEAVE_DAILY_CHANCE) {
+                let days = generator.gen_range(MIN_SICK_LEAVE_DAYS..=MAX_SICK_LEAVE_DAYS);
+                let end = date.checked_add_days(Days::new(days - 1)).unwrap();
+                if !overlaps(&absences, date, end) {
+                    absences.push(Absence {
+                        policeman_id: policeman.person.id,
+                        kind: AbsenceKind::SickLeave,
+                        start_date: date,
+                        end_date: end,
+                    });
+                }
+                date = end;
+            }
+            date = date.checked_add_days(Days::new(1)).unwrap();
+        }
+        absences.sort_by_key(|absence| absence.start_date);
+
+        self.policemen.push(PolicemanRoster {
+            rotation_offset: generator.gen_range(0..ROTATION_LENGTH_DAYS),
+            absences,
+        });
+    }
+
+    /// Whether the policeman is scheduled for a shift at the given time and is not absent.
+    /// Employment and resignation are not checked, the caller has to skip officers out of service.
+    pub fn is_on_duty(&self, policeman_id: usize, time: DateTime<Utc>) -> bool {
+        let shift_date = if time.hour() < DAY_SHIFT_START_HOUR {
+            time.date_naive().pred_opt().unwrap()
+        } else {
+            time.date_naive()
+        };
+        let current_shift =
+            if time.hour() >= DAY_SHIFT_START_HOUR && time.hour() < NIGHT_SHIFT_START_HOUR {
+                Shift::Day
+            } else {
+                Shift::Night
+            };
+
+        self.scheduled_shift(policeman_id, shift_date) == Some(current_shift)
+            && self.absence(policeman_id, shift_date).is_none()
+    }
+
+    /// The shift starting on the given date, if any.
+    pub fn scheduled_shift(&self, policeman_id: usize, date: NaiveDate) -> Option<Shift> {
+        let roster = self.policemen.get(policeman_id).unwrap();
+        let days = date.num_days_from_ce() as i64 + roster.rotation_offset;
+        match days.rem_euclid(ROTATION_LENGTH_DAYS) {
+            DAY_SHIFT_ROTATION_DAY => Some(Shift::Day),
+            NIGHT_SHIFT_ROTATION_DAY => Some(Shift::Night),
+            _ => None,
+        }
+    }
+
+    pub fn absence(&self, policeman_id: usize, date: NaiveDate) -> Option<&Absence> {
+        self.policemen
+            .get(policeman_id)
+            .unwrap()
+            .absences
+            .iter()
+            .find(|absence| absence.start_date <= date && date <= absence.end_date)
+    }
+
+    /// Absences which started before the given time.
+    pub fn absences_before(&self, time: DateTime<Utc>) -> impl Iterator<Item = &Absence> {
+        self.policemen
+            .iter()
+            .flat_map(|roster| roster.absences.iter())
+            .filter(move |absence| absence.start_date < time.date_naive())
+    }
+
+    /// The planned shifts of the policemen which started before the given time.
+    pub fn entries_before(&self, policemen: &[Policeman], time: DateTime<Utc>) -> Vec<RosterEntry> {
+        let mut entries = vec![];
+        for policeman in policemen {
+            let id = policeman.person.id;
+            let end = policeman.resignment_date.min(time);
+            let mut date = policeman.employment_date.date_naive();
+            while date <= end.date_naive() {
+                match self.scheduled_shift(id, date) {
+                    Some(shift) if shift_start(date, shift) < end => {
+                        entries.push(RosterEntry {
+                            policeman_id: id,
+                            date,
+                            shift,
+                            absence: self.absence(id, date).map(|absence| absence.kind),
+                        });
+                    }
+                    _ => {}
+                }
+                date = date.succ_opt().unwrap();
+            }
+        }
+
+        entries
+    }
+}
+
+fn shift_start(date: NaiveDate, shift: Shift) -> DateTime<Utc> {
+    let start_hour = match shift {
+        Shift::Day => DAY_SHIFT_START_HOUR,
+        Shift::Night => NIGHT_SHIFT_START_HOUR,
+    };
+    date.and_time(NaiveTime::from_hms_opt(start_hour, 0, 0).unwrap())
+        .and_utc()
+}
+
+fn overlaps(absences: &[Absence], start: NaiveDate, end: NaiveDate) -> bool {
+    absences
+        .iter()
+        .any(|absence| absence.start_date <= end && start <= absence.end_date)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, Duration, TimeZone, Utc};
+
+    use crate::roster::{
+        Absence, AbsenceKind, PolicemanRoster, Roster, RosterEntry, Shift, DAY_SHIFT_ROTATION_DAY,
+        ROTATION_LENGTH_DAYS,
+    };
+
+    #[test]
+    fn should_be_on_duty_for_one_day_and_one_night_shift_per_rotation_unless_absent() {
+        let start_time = Utc.with_ymd_and_hms(2015, 6, 1, 0, 0, 0).unwrap();
+        let start_date = start_time.date_naive();
+        // The rotation starts with the day shift on the start date
+        let rotation_offset = (DAY_SHIFT_ROTATION_DAY - start_date.num_days_from_ce() as i64)
+            .rem_euclid(ROTATION_LENGTH_DAYS);
+        let roster = Roster {
+            policemen: vec![
+                PolicemanRoster {
+                    rotation_offset,
+                    absences: vec![],
+                },
+                PolicemanRoster {
+                    rotation_offset,
+                    absences: vec![Absence {
+                        policeman_id: 1,
+                        kind: AbsenceKind::SickLeave,
+                        start_date,
+                        end_date: start_date,
+                    }],
+                },
+            ],
+        };
+
+        let on_duty_hours = |policeman_id| {
+            (0..24 * ROTATION_LENGTH_DAYS)
+                .map(|hour| start_time + Duration::hours(hour))
+                .filter(|time| roster.is_on_duty(policeman_id, *time))
+                .count()
+        };
+
+        assert_eq!(on_duty_hours(0), 24);
+        // Only the night shift after the day of absence
+        assert_eq!(on_duty_hours(1), 12);
+        assert!(roster.is_on_duty(0, start_time + Duration::hours(12)));
+        assert!(!roster.is_on_duty(1, start_time + Duration::hours(12)));
+        assert!(roster.is_on_duty(1, start_time + Duration::hours(44)));
+    }
+
+    #[test]
+    fn should_count_only_the_worked_part_of_the_shifts_on_duty() {
+        let night_start = Utc.with_ymd_and_hms(2015, 6, 1, 19, 0, 0).unwrap();
+        let mut entry = RosterEntry {
+            policeman_id: 0,
+            date: night_start.date_naive(),
+            shift: Shift::Night,
+            absence: None,
+        };
+
+        assert_eq!(entry.on_duty_seconds_before(night_start), 0);
+        assert_eq!(
+            entry.on_duty_seconds_before(night_start + Duration::hours(5)),
+            5 * 3600
+        );
+        assert_eq!(
+            entry.on_duty_seconds_before(night_start + Duration::days(2)),
+            12 * 3600
+        );
+        entry.absence = Some(AbsenceKind::SickLeave);
+        assert_eq!(
+            entry.on_duty_seconds_before(night_start + Duration::days(2)),
+            0
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::io::{read_source_file, SOURCE_KEYS};
+use crate::source_format::SourceFormats;
+
+// Joins the values of a key made of several columns
+const KEY_DELIMITER: &str = "/";
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ChangeKind {
+    Inserted,
+    Updated,
+    Deleted,
+}
+
+/// A row inserted or deleted between two snapshots, or a single column of an updated row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowChange {
+    pub file_suffix: &'static str,
+    /// The key columns of the row joined with '/'.
+    pub key: String,
+    pub kind: ChangeKind,
+    /// Only for updates.
+    pub column: Option<usize>,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+impl ChangeKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ChangeKind::Inserted => "INSERTED",
+            ChangeKind::Updated => "UPDATED",
+            ChangeKind::Deleted => "DELETED",
+        }
+    }
+}
+
+/// Compares every source file of two snapshots, the formats of both are taken into account.
+pub fn diff_snapshots(
+    old_snapshot_name: &str,
+    new_snapshot_name: &str,
+    formats: SourceFormats,
+) -> Vec<RowChange> {
+    SOURCE_KEYS
+        .iter()
+        .flat_map(|(file_suffix, key_columns)| {
+            diff_rows(
+                file_suffix,
+                key_columns,
+                &read_source_file(old_snapshot_name, file_suffix, formats),
+                &read_source_file(new_snapshot_name, file_suffix, formats),
+            )
+        })
+        .collect()
+}
+
+/// Deletions and updates in the order of the old rows, followed by insertions in the order of the
+/// new ones. Only the first of the rows with the same key is compared.
+pub fn diff_rows(
+    file_suffix: &'static str,
+    key_columns: &[usize],
+    old_rows: &[Vec<String>],
+    new_rows: &[Vec<String>],
+) -> Vec<RowChange> {
+    let key = |row: &Vec<String>| {
+        key_columns
+            .iter()
+            .map(|column| row[*column].as_str())
+            .collect::<Vec<_>>()
+            .join(KEY_DELIMITER)
+    };
+    let mut new_rows_by_key = HashMap::new();
+    for row in new_rows {
+        new_rows_by_key.entry(key(row)).or_insert(row);
+    }
+    let old_keys = old_rows.iter().map(key).collect::<HashSet<_>>();
+
+    let mut changes = vec![];
+    let mut change = |key: &str, kind, column, old_value: &str, new_value: &str| {
+        changes.push(RowChange {
+            file_suffix,
+            key: key.to_string(),
+            kind,
+            column,
+            old_value: old_value.to_string(),
+            new_value: new_value.to_string(),
+        })
+    };
+    let mut compared_keys = HashSet::new();
+    for old_row in old_rows {
+        let row_key = key(old_row);
+        if !compared_keys.insert(row_key.clone()) {
+            continue;
+        }
+        let Some(new_row) = new_rows_by_key.get(&row_key) else {
+            change(&row_key, ChangeKind::Deleted, None, "", "");
+            continue;
+        };
+        for column in 0..old_row.len().max(new_row.len()) {
+            let old_value = old_row.get(column).map_or("", String::as_str);
+            let new_value = new_row.get(column).map_or("", String::as_str);
+            if old_value != new_value {
+                change(
+                    &row_key,
+                    ChangeKind::Updated,
+                    Some(column),
+                    old_value,
+                    new_value,
+                );
+            }
+        }
+    }
+    for new_row in new_rows {
+        let row_key = key(new_row);
+        if !old_keys.contains(&row_key) && compared_keys.insert(row_key.clone()) {
+            change(&row_key, ChangeKind::Inserted, None, "", "");
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::random;
+
+    use crate::defect::{DefectColumns, DefectInjector, DefectRates};
+    use crate::snapshot_diff::{diff_rows, ChangeKind};
+
+    #[test]
+    fn should_list_inserted_updated_and_deleted_rows() {
+        let rows = |rows: &[[&str; 3]]| {
+            rows.iter()
+                .map(|row| row.map(str::to_string).to_vec())
+                .collect::<Vec<_>>()
+        };
+        let old_rows = rows(&[["1", "A", "x"], ["2", "B", "y"], ["3", "C", "z"]]);
+        let new_rows = rows(&[["3", "C", "w"], ["1", "A", "x"], ["4", "D", "v"]]);
+
+        let changes = diff_rows("test.csv", &[0, 1], &old_rows, &new_rows)
+            .into_iter()
+            .map(|change| (change.key, change.kind, change.column, change.new_value))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            changes,
+            vec![
+                ("2/B".to_string(), ChangeKind::Deleted, None, "".to_string()),
+                (
+                    "3/C".to_string(),
+                    ChangeKind::Updated,
+                    Some(2),
+                    "w".to_string()
+                ),
+                (
+                    "4/D".to_string(),
+                    ChangeKind::Inserted,
+                    None,
+                    "".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_not_list_changes_of_unchanged_rows_with_defects() {
+        let rates = DefectRates {
+            duplicated_row: 0.2,
+            missing_field: 0.2,
+            typo: 0.2,
+            swapped_names: 0.2,
+            malformed_pesel: 0.2,
+            malformed_phone_number: 0.2,
+            out_of_range_date: 0.2,
+            orphan_foreign_key: 0.2,
+        };
+        let columns = DefectColumns {
+            mandatory: &[1, 2],
+            typos: &[1, 2],
+            names: Some((1, 2)),
+            pesel: Some(3),
+            phone_number: Some(4),
+            dates: &[5],
+            foreign_keys: &[6],
+        };
+        let rows = (0..100)
+            .map(|id| {
+                [
+                    &id.to_string(),
+                    "JAN",
+                    "KOWALSKI",
+                    "85010112345",
+                    "600100200",
+                    "1985-01-01",
+                    "7",
+                ]
+                .map(str::to_string)
+                .to_vec()
+            })
+            .collect::<Vec<_>>();
+        let seed = random();
+
+        let old_rows =
+            DefectInjector::new(rates, seed).inject("test.csv", &columns, &[0], rows.clone());
+        let new_rows = DefectInjector::new(rates, seed).inject("test.csv", &columns, &[0], rows);
+
+        assert_ne!(old_rows.len(), 100);
+        assert_eq!(diff_rows("test.csv", &[0], &old_rows, &new_rows), vec![]);
+    }
+}
@@ -0,0 +1,338 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Europe::Warsaw;
+use encoding_rs::WINDOWS_1250;
+use once_cell::sync::Lazy;
+
+pub const ISO_DATE_FORMAT: &str = "%Y-%m-%d";
+// The way DateTime<Utc> is written by to_string
+pub const ISO_DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S UTC";
+const POLISH_DATE_FORMAT: &str = "%d.%m.%Y";
+const POLISH_DATETIME_FORMAT: &str = "%d.%m.%Y %H:%M:%S";
+const LOCAL_DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+// Excel counts days from 1899-12-30, because of its 1900 leap year bug
+static EXCEL_EPOCH: Lazy<NaiveDate> = Lazy::new(|| NaiveDate::from_ymd_opt(1899, 12, 30).unwrap());
+const SECONDS_PER_DAY: f64 = 86400.0;
+const EXCEL_SERIAL_PRECISION: usize = 6;
+
+/// A system which produces some of the source files.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Source {
+    Database,
+    /// The dispatch system, which tracks the patrols.
+    Dispatch,
+    /// The emergency call center, which takes the reports.
+    CallCenter,
+    /// The spreadsheet kept by the staff of the personnel department.
+    PolicemenSpreadsheet,
+    /// The spreadsheet kept by the staff of the vehicle pool.
+    VehicleSpreadsheet,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DateFormat {
+    /// "2015-06-01" and "2015-06-01 12:00:00 UTC".
+    Iso,
+    /// "01.06.2015" and "01.06.2015 12:00:00", in UTC.
+    Polish,
+    /// Days since 1899-12-30, with the time of day as a fraction.
+    ExcelSerial,
+    /// Seconds since 1970-01-01, dates at midnight UTC.
+    EpochSeconds,
+    /// "2015-06-01 14:00:00" in Europe/Warsaw time, without the offset. The hour repeated at the
+    /// end of summer time is lossy: a time in the second 02:xx hour is read back one hour earlier.
+    WarsawLocal,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TextEncoding {
+    Utf8,
+    /// Characters outside of it are written as numeric character references.
+    Windows1250,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct SourceFormat {
+    pub date_format: DateFormat,
+    /// Columns are separated with ';' if this is ','.
+    pub decimal_separator: char,
+    pub encoding: TextEncoding,
+    pub line_ending: LineEnding,
+}
+
+/// Which columns of an exported file hold values depending on the format.
+#[derive(Debug, Copy, Clone)]
+pub struct FormattedColumns {
+    pub dates: &'static [usize],
+    pub datetimes: &'static [usize],
+    pub decimals: &'static [usize],
+}
+
+/// How each source system writes its files.
+#[derive(Debug, Copy, Clone)]
+pub struct SourceFormats {
+    pub database: SourceFormat,
+    pub dispatch: SourceFormat,
+    pub call_center: SourceFormat,
+    pub policemen_spreadsheet: SourceFormat,
+    pub vehicle_spreadsheet: SourceFormat,
+}
+
+impl SourceFormat {
+    /// The format all the files are written in before being converted.
+    pub const DEFAULT: SourceFormat = SourceFormat {
+        date_format: DateFormat::Iso,
+        decimal_separator: '.',
+        encoding: TextEncoding::Utf8,
+        line_ending: LineEnding::Lf,
+    };
+
+    pub fn column_delimiter(&self) -> &'static str {
+        if self.decimal_separator == ',' {
+            ";"
+        } else {
+            ","
+        }
+    }
+
+    pub fn line_ending(&self) -> &'static str {
+        match self.line_ending {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+
+    pub fn format_date(&self, date: NaiveDate) -> String {
+        match self.date_format {
+            DateFormat::Iso | DateFormat::WarsawLocal => date.format(ISO_DATE_FORMAT).to_string(),
+            DateFormat::Polish => date.format(POLISH_DATE_FORMAT).to_string(),
+            DateFormat::ExcelSerial => (date - *EXCEL_EPOCH).num_days().to_string(),
+            DateFormat::EpochSeconds => date
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+                .and_utc()
+                .timestamp()
+                .to_string(),
+        }
+    }
+
+    pub fn format_datetime(&self, datetime: DateTime<Utc>) -> String {
+        match self.date_format {
+            DateFormat::Iso => datetime.format(ISO_DATETIME_FORMAT).to_string(),
+            DateFormat::Polish => datetime.format(POLISH_DATETIME_FORMAT).to_string(),
+            DateFormat::ExcelSerial => {
+                let days = (datetime.naive_utc() - EXCEL_EPOCH.and_hms_opt(0, 0, 0).unwrap())
+                    .num_seconds() as f64
+                    / SECONDS_PER_DAY;
+                self.format_decimal(&format!("{:.*}", EXCEL_SERIAL_PRECISION, days))
+            }
+            DateFormat::EpochSeconds => datetime.timestamp().to_string(),
+            DateFormat::WarsawLocal => datetime
+                .with_timezone(&Warsaw)
+                .format(LOCAL_DATETIME_FORMAT)
+                .to_string(),
+        }
+    }
+
+    /// Rewrites a number written with a '.' to use the decimal separator.
+    pub fn format_decimal(&self, value: &str) -> String {
+        value.replace('.', &self.decimal_separator.to_string())
+    }
+
+    /// Rewrites a value written in the default format, values which cannot be parsed are kept.
+    pub fn reformat_date(&self, value: &str) -> String {
+        match NaiveDate::parse_from_str(value, ISO_DATE_FORMAT) {
+            Ok(date) => self.format_date(date),
+            Err(_) => value.to_string(),
+        }
+    }
+
+    /// Rewrites a value written in the default format, values which cannot be parsed are kept.
+    pub fn reformat_datetime(&self, value: &str) -> String {
+        match NaiveDateTime::parse_from_str(value, ISO_DATETIME_FORMAT) {
+            Ok(datetime) => self.format_datetime(datetime.and_utc()),
+            Err(_) => value.to_string(),
+        }
+    }
+
+    pub fn reformat_row(&self, columns: &FormattedColumns, row: &mut [String]) {
+        for column in columns.dates {
+            row[*column] = self.reformat_date(&row[*column]);
+        }
+        for column in columns.datetimes {
+            row[*column] = self.reformat_datetime(&row[*column]);
+        }
+        for column in columns.decimals {
+            row[*column] = self.format_decimal(&row[*column]);
+        }
+    }
+
+    pub fn parse_date(&self, value: &str) -> Option<NaiveDate> {
+        match self.date_format {
+            DateFormat::Iso | DateFormat::WarsawLocal => {
+                NaiveDate::parse_from_str(value, ISO_DATE_FORMAT).ok()
+            }
+            DateFormat::Polish => NaiveDate::parse_from_str(value, POLISH_DATE_FORMAT).ok(),
+            DateFormat::ExcelSerial => {
+                Some(*EXCEL_EPOCH + Duration::days(value.parse::<i64>().ok()?))
+            }
+            DateFormat::EpochSeconds => {
+                Some(DateTime::from_timestamp(value.parse::<i64>().ok()?, 0)?.date_naive())
+            }
+        }
+    }
+
+    /// Ambiguous local times, during the change from summer time, are taken as the earlier ones.
+    pub fn parse_datetime(&self, value: &str) -> Option<DateTime<Utc>> {
+        match self.date_format {
+            DateFormat::Iso => NaiveDateTime::parse_from_str(value, ISO_DATETIME_FORMAT)
+                .ok()
+                .map(|datetime| datetime.and_utc()),
+            DateFormat::Polish => NaiveDateTime::parse_from_str(value, POLISH_DATETIME_FORMAT)
+                .ok()
+                .map(|datetime| datetime.and_utc()),
+            DateFormat::ExcelSerial => {
+                let days = value
+                    .replace(self.decimal_separator, ".")
+                    .parse::<f64>()
+                    .ok()?;
+                let seconds = (days * SECONDS_PER_DAY).round() as i64;
+                Some(EXCEL_EPOCH.and_hms_opt(0, 0, 0)?.and_utc() + Duration::seconds(seconds))
+            }
+            DateFormat::EpochSeconds => DateTime::from_timestamp(value.parse::<i64>().ok()?, 0),
+            DateFormat::WarsawLocal => {
+                let datetime = NaiveDateTime::parse_from_str(value, LOCAL_DATETIME_FORMAT).ok()?;
+                Warsaw
+                    .from_local_datetime(&datetime)
+                    .earliest()
+                    .map(|datetime| datetime.with_timezone(&Utc))
+            }
+        }
+    }
+
+    /// Rewrites a row read from a file in this format back to the default one.
+    pub fn normalize_row(&self, columns: &FormattedColumns, row: &mut [String]) {
+        for column in columns.dates {
+            if let Some(date) = self.parse_date(&row[*column]) {
+                row[*column] = date.format(ISO_DATE_FORMAT).to_string();
+            }
+        }
+        for column in columns.datetimes {
+            if let Some(datetime) = self.parse_datetime(&row[*column]) {
+                row[*column] = datetime.format(ISO_DATETIME_FORMAT).to_string();
+            }
+        }
+        for column in columns.decimals {
+            row[*column] = row[*column].replace(self.decimal_separator, ".");
+        }
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        match self.encoding {
+            TextEncoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+            TextEncoding::Windows1250 => WINDOWS_1250.decode(bytes).0.into_owned(),
+        }
+    }
+
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        match self.encoding {
+            TextEncoding::Utf8 => text.as_bytes().to_vec(),
+            TextEncoding::Windows1250 => WINDOWS_1250.encode(text).0.into_owned(),
+        }
+    }
+}
+
+impl SourceFormats {
+    pub fn get(&self, source: Source) -> &SourceFormat {
+        match source {
+            Source::Database => &self.database,
+            Source::Dispatch => &self.dispatch,
+            Source::CallCenter => &self.call_center,
+            Source::PolicemenSpreadsheet => &self.policemen_spreadsheet,
+            Source::VehicleSpreadsheet => &self.vehicle_spreadsheet,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use crate::source_format::{DateFormat, FormattedColumns, SourceFormat};
+
+    #[test]
+    fn should_reformat_default_values() {
+        let mut format = SourceFormat::DEFAULT;
+        let datetime = Utc.with_ymd_and_hms(2015, 6, 1, 12, 0, 0).unwrap();
+        assert_eq!(
+            format.reformat_datetime(&datetime.to_string()),
+            datetime.to_string()
+        );
+
+        format.date_format = DateFormat::WarsawLocal;
+        assert_eq!(
+            format.reformat_datetime(&datetime.to_string()),
+            "2015-06-01 14:00:00"
+        );
+        format.date_format = DateFormat::ExcelSerial;
+        format.decimal_separator = ',';
+        assert_eq!(
+            format.reformat_datetime(&datetime.to_string()),
+            "42156,500000"
+        );
+        assert_eq!(format.reformat_date("2015-06-01"), "42156");
+        format.date_format = DateFormat::Polish;
+        assert_eq!(format.reformat_date("2015-06-01"), "01.06.2015");
+        assert_eq!(format.reformat_date(""), "");
+    }
+
+    #[test]
+    fn should_normalize_formatted_rows() {
+        let columns = FormattedColumns {
+            dates: &[0],
+            datetimes: &[1],
+            decimals: &[2],
+        };
+        let row = ["2015-06-01", "2015-10-25 00:30:00 UTC", "1.5"].map(str::to_string);
+
+        for date_format in [
+            DateFormat::Iso,
+            DateFormat::Polish,
+            DateFormat::ExcelSerial,
+            DateFormat::EpochSeconds,
+            DateFormat::WarsawLocal,
+        ] {
+            let format = SourceFormat {
+                date_format,
+                decimal_separator: ',',
+                ..SourceFormat::DEFAULT
+            };
+            let mut formatted_row = row.clone();
+            format.reformat_row(&columns, &mut formatted_row);
+            format.normalize_row(&columns, &mut formatted_row);
+            assert_eq!(formatted_row, row);
+        }
+    }
+
+    #[test]
+    fn should_read_the_repeated_hour_of_the_summer_time_end_as_the_first_one() {
+        let format = SourceFormat {
+            date_format: DateFormat::WarsawLocal,
+            ..SourceFormat::DEFAULT
+        };
+        // 02:30 in winter time, after the clocks went back from 03:00 to 02:00
+        let datetime = Utc.with_ymd_and_hms(2015, 10, 25, 1, 30, 0).unwrap();
+
+        let formatted = format.format_datetime(datetime);
+        assert_eq!(formatted, "2015-10-25 02:30:00");
+        assert_eq!(
+            format.parse_datetime(&formatted),
+            Some(Utc.with_ymd_and_hms(2015, 10, 25, 0, 30, 0).unwrap())
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use once_cell::sync::Lazy;
+use std::str::FromStr;
+
+use crate::place::{get_city, Coordinates, Place};
+
+const STATIONS_STRING: &str = include_str!("../data/stations.txt");
+// Stations further apart would not arrive in time, so they do not provide mutual aid
+const MAX_MUTUAL_AID_DISTANCE_KM: f64 = 6.0;
+
+static STATIONS: Lazy<Vec<Station>> = Lazy::new(|| create_stations_from_str(STATIONS_STRING));
+
+#[derive(Debug, Copy, Clone)]
+pub struct Station {
+    pub id: usize,
+    pub name: &'static str,
+    pub city: &'static str,
+    pub coordinates: Coordinates,
+    pub policemen_count: usize,
+    pub vehicles_count: usize,
+}
+
+pub fn get_all_stations() -> &'static Vec<Station> {
+    Lazy::force(&STATIONS)
+}
+
+/// The station responsible for a place is the one closest to it.
+pub fn get_responsible_station(place: &Place) -> &'static Station {
+    STATIONS
+        .iter()
+        .min_by(|first, second| {
+            let first_distance = first.coordinates.distance_km(&place.coordinates);
+            let second_distance = second.coordinates.distance_km(&place.coordinates);
+            first_distance.total_cmp(&second_distance)
+        })
+        .unwrap()
+}
+
+impl Station {
+    /// The station itself followed by the stations close enough to provide mutual aid, from the
+    /// closest one.
+    pub fn with_neighbours(&self) -> Vec<&'static Station> {
+        let mut stations = STATIONS
+            .iter()
+            .filter(|station| {
+                station.coordinates.distance_km(&self.coordinates) <= MAX_MUTUAL_AID_DISTANCE_KM
+            })
+            .collect::<Vec<_>>();
+        stations.sort_by(|first, second| {
+            let first_distance = first.coordinates.distance_km(&self.coordinates);
+            let second_distance = second.coordinates.distance_km(&self.coordinates);
+            first_distance.total_cmp(&second_distance)
+        });
+        stations
+    }
+}
+
+fn create_stations_from_str(data: &'static str) -> Vec<Station> {
+    let mut counter = 0;
+    data.split('\n')
+        .filter_map(|line| {
+            let mut line_split = line.split(',');
+            let name = line_split.next()?;
+            let city = line_split.next()?;
+            let policemen_count = usize::from_str(line_split.next()?).ok()?;
+            let vehicles_count = usize::from_str(line_split.next()?).ok()?;
+
+            let station = Some(Station {
+                id: counter,
+                name,
+                city,
+                coordinates: get_city(city).center,
+                policemen_count,
+                vehicles_count,
+            });
+            counter += 1;
+            station
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::place::get_all_places;
+    use crate::station::{get_all_stations, get_responsible_station, MAX_MUTUAL_AID_DISTANCE_KM};
+
+    #[test]
+    fn should_route_reports_to_the_closest_station() {
+        for place in get_all_places() {
+            let responsible_distance = get_responsible_station(place)
+                .coordinates
+                .distance_km(&place.coordinates);
+            assert!(get_all_stations().iter().all(|station| {
+                responsible_distance <= station.coordinates.distance_km(&place.coordinates)
+            }));
+        }
+        let rokitki_place = get_all_places()
+            .iter()
+            .find(|place| place.city == "Rokitki")
+            .unwrap();
+        assert_eq!(get_responsible_station(rokitki_place).city, "Rokitki");
+    }
+
+    #[test]
+    fn should_ask_only_nearby_stations_for_aid_starting_with_the_station_itself() {
+        for station in get_all_stations() {
+            let neighbours = station.with_neighbours();
+            let distances = neighbours
+                .iter()
+                .map(|neighbour| neighbour.coordinates.distance_km(&station.coordinates))
+                .collect::<Vec<_>>();
+
+            assert_eq!(neighbours.first().unwrap().id, station.id);
+            assert!(distances.windows(2).all(|pair| pair[0] <= pair[1]));
+            assert!(distances
+                .iter()
+                .all(|distance| *distance <= MAX_MUTUAL_AID_DISTANCE_KM));
+        }
+        let rokitki = get_all_stations()
+            .iter()
+            .find(|station| station.city == "Rokitki")
+            .unwrap();
+        let aiding_cities = rokitki
+            .with_neighbours()
+            .iter()
+            .map(|station| station.city)
+            .collect::<Vec<_>>();
+        assert_eq!(aiding_cities, ["Rokitki", "Tczew"]);
+    }
+}
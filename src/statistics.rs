@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+
+use crate::patrol::Patrol;
+use crate::person::Policeman;
+use crate::report::{get_all_report_types, Report};
+use crate::roster::RosterEntry;
+use crate::vehicle::Vehicle;
+
+pub const PERCENTILES: [u32; 4] = [50, 90, 95, 99];
+const MONTH_FORMAT: &str = "%Y-%m";
+const SECONDS_PER_MINUTE: f64 = 60.0;
+
+/// A summary of the exported data, i.e. the recorded reports and patrols which are not deleted.
+#[derive(Debug, Clone)]
+pub struct Statistics {
+    pub snapshot_date: DateTime<Utc>,
+    pub reports_by_type: Vec<(&'static str, usize)>,
+    pub reports_by_month: Vec<(String, usize)>,
+    /// Seconds from the report to the arrival of a patrol, for each of the PERCENTILES.
+    pub response_time_percentiles: Vec<f64>,
+    /// Seconds a patrol request waited in the dispatch queue, for each of the PERCENTILES.
+    pub dispatch_wait_percentiles: Vec<f64>,
+    pub mean_dispatch_wait_seconds: f64,
+    /// The share of the rostered time, apart from the leaves, the officers spent on patrols.
+    pub officer_utilization: f64,
+    /// The share of the time in the fleet the vehicles spent on patrols.
+    pub vehicle_utilization: f64,
+    /// The officers employed at the start of each month.
+    pub headcount_by_month: Vec<(String, usize)>,
+}
+
+impl Statistics {
+    pub fn build(
+        snapshot_date: DateTime<Utc>,
+        data_start_date: DateTime<Utc>,
+        policemen: &[Policeman],
+        roster_entries: &[RosterEntry],
+        vehicles: &[Vehicle],
+        reports: &[Report],
+        patrols: &[Patrol],
+    ) -> Self {
+        let reports = reports
+            .iter()
+            .filter(|report| report.audit.created_at < snapshot_date && !report.is_deleted)
+            .collect::<Vec<_>>();
+        let patrols = patrols
+            .iter()
+            .filter(|patrol| patrol.audit.created_at < snapshot_date)
+            .collect::<Vec<_>>();
+        let months = months_between(data_start_date.date_naive(), snapshot_date.date_naive());
+
+        let reports_by_type = get_all_report_types()
+            .map(|report_type| {
+                let count = reports
+                    .iter()
+                    .filter(|report| report.recorded_report_type() == report_type)
+                    .count();
+                (report_type, count)
+            })
+            .collect();
+        let reports_by_month = months
+            .iter()
+            .map(|month| {
+                let count = reports
+                    .iter()
+                    .filter(|report| is_same_month(report.time.date_naive(), *month))
+                    .count();
+                (month.format(MONTH_FORMAT).to_string(), count)
+            })
+            .collect();
+
+        let reports_by_id = reports
+            .iter()
+            .map(|report| (report.id, *report))
+            .collect::<HashMap<_, _>>();
+        let response_times = patrols
+            .iter()
+            .filter(|patrol| patrol.arrival_time < snapshot_date)
+            .filter_map(|patrol| {
+                let report = reports_by_id.get(&patrol.report_id)?;
+                Some((patrol.arrival_time - report.time).num_seconds() as f64)
+            })
+            .collect::<Vec<_>>();
+        let dispatch_waits = patrols
+            .iter()
+            .map(|patrol| (patrol.sending_time - patrol.queued_time).num_seconds() as f64)
+            .collect::<Vec<_>>();
+
+        let patrol_seconds = |patrol: &Patrol| {
+            (patrol.finish_time.min(snapshot_date) - patrol.sending_time).num_seconds() as f64
+        };
+        let officer_patrol_seconds = patrols
+            .iter()
+            .map(|patrol| patrol_seconds(patrol) * patrol.policemen_ids.len() as f64)
+            .sum::<f64>();
+        let on_duty_seconds = roster_entries
+            .iter()
+            .map(|entry| entry.on_duty_seconds_before(snapshot_date) as f64)
+            .sum::<f64>();
+        let vehicle_patrol_seconds = patrols
+            .iter()
+            .map(|patrol| patrol_seconds(patrol))
+            .sum::<f64>();
+        let fleet_seconds = vehicles
+            .iter()
+            .filter(|vehicle| vehicle.commissioning_date < snapshot_date)
+            .map(|vehicle| {
+                let end_date = vehicle
+                    .decommissioning_date
+                    .unwrap_or(snapshot_date)
+                    .min(snapshot_date);
+                (end_date - vehicle.commissioning_date).num_seconds() as f64
+            })
+            .sum::<f64>();
+
+        let headcount_by_month = months
+            .iter()
+            .map(|month| {
+                let month_start = month.and_hms_opt(0, 0, 0).unwrap().and_utc();
+                let count = policemen
+                    .iter()
+                    .filter(|policeman| {
+                        policeman.employment_date <= month_start
+                            && month_start < policeman.resignment_date
+                    })
+                    .count();
+                (month.format(MONTH_FORMAT).to_string(), count)
+            })
+            .collect();
+
+        Self {
+            snapshot_date,
+            reports_by_type,
+            reports_by_month,
+            response_time_percentiles: percentiles(response_times),
+            mean_dispatch_wait_seconds: mean(&dispatch_waits),
+            dispatch_wait_percentiles: percentiles(dispatch_waits),
+            officer_utilization: ratio(officer_patrol_seconds, on_duty_seconds),
+            vehicle_utilization: ratio(vehicle_patrol_seconds, fleet_seconds),
+            headcount_by_month,
+        }
+    }
+
+    /// The main figures in a single line, the full summary is its Display.
+    pub fn summary(&self) -> String {
+        let reports_count = self
+            .reports_by_type
+            .iter()
+            .map(|(_, count)| count)
+            .sum::<usize>();
+        let median_response_seconds = self
+            .response_time_percentiles
+            .first()
+            .copied()
+            .unwrap_or_default();
+        format!(
+            "{reports_count} reports, median response {:.1} minutes, officer utilization {:.1}%, \
+            vehicle utilization {:.1}%",
+            median_response_seconds / SECONDS_PER_MINUTE,
+            self.officer_utilization * 100.0,
+            self.vehicle_utilization * 100.0
+        )
+    }
+}
+
+impl Display for Statistics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Statistics at {}", self.snapshot_date)?;
+        writeln!(f, "Reports by type:")?;
+        for (report_type, count) in &self.reports_by_type {
+            writeln!(f, "  {report_type}: {count}")?;
+        }
+        writeln!(f, "Reports by month:")?;
+        for (month, count) in &self.reports_by_month {
+            writeln!(f, "  {month}: {count}")?;
+        }
+        writeln!(f, "Response time percentiles (minutes):")?;
+        for (percentile, seconds) in PERCENTILES.iter().zip(&self.response_time_percentiles) {
+            writeln!(f, "  p{percentile}: {:.1}", seconds / SECONDS_PER_MINUTE)?;
+        }
+        writeln!(f, "Dispatch wait percentiles (minutes):")?;
+        for (percentile, seconds) in PERCENTILES.iter().zip(&self.dispatch_wait_percentiles) {
+            writeln!(f, "  p{percentile}: {:.1}", seconds / SECONDS_PER_MINUTE)?;
+        }
+        writeln!(
+            f,
+            "Mean dispatch wait: {:.1} minutes",
+            self.mean_dispatch_wait_seconds / SECONDS_PER_MINUTE
+        )?;
+        writeln!(
+            f,
+            "Officer utilization: {:.1}%",
+            self.officer_utilization * 100.0
+        )?;
+        writeln!(
+            f,
+            "Vehicle utilization: {:.1}%",
+            self.vehicle_utilization * 100.0
+        )?;
+        writeln!(f, "Headcount by month:")?;
+        for (month, count) in &self.headcount_by_month {
+            writeln!(f, "  {month}: {count}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The first days of the months from the one of the start date to the one of the end date.
+fn months_between(start_date: NaiveDate, end_date: NaiveDate) -> Vec<NaiveDate> {
+    let mut month = start_date.with_day(1).unwrap();
+    let mut months = vec![];
+    while month <= end_date {
+        months.push(month);
+        month = month.checked_add_months(Months::new(1)).unwrap();
+    }
+    months
+}
+
+fn is_same_month(date: NaiveDate, month: NaiveDate) -> bool {
+    date.year() == month.year() && date.month() == month.month()
+}
+
+/// The nearest-rank percentiles, all 0 if there are no values.
+fn percentiles(mut values: Vec<f64>) -> Vec<f64> {
+    values.sort_by(f64::total_cmp);
+    PERCENTILES
+        .iter()
+        .map(|percentile| {
+            let rank = (*percentile as f64 / 100.0 * values.len() as f64).ceil() as usize;
+            values
+                .get(rank.saturating_sub(1))
+                .copied()
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    ratio(values.iter().sum(), values.len() as f64)
+}
+
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+    if denominator > 0.0 {
+        numerator / denominator
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::statistics::percentiles;
+
+    #[test]
+    fn should_calculate_nearest_rank_percentiles() {
+        let values = (1..=200).rev().map(f64::from).collect::<Vec<_>>();
+
+        assert_eq!(percentiles(values), vec![100.0, 180.0, 190.0, 198.0]);
+        assert_eq!(percentiles(vec![]), vec![0.0; 4]);
+    }
+}
@@ -20,12 +20,8 @@ impl StringOccurrences {
             .split('\n')
             .filter_map(|line| {
                 let mut line_split = line.split(',');
-                let Some(entry) = line_split.next() else {
-                    return None;
-                };
-                let Some(occurrences_string) = line_split.next() else {
-                    return None;
-                };
+                let entry = line_split.next()?;
+                let occurrences_string = line_split.next()?;
 
                 let Ok(occurrences) = u32::from_str(occurrences_string) else {
                     panic!("The second column should be convertible to an u32. Found string \"{}\" in data:\n{}", occurrences_string, data);
@@ -43,7 +39,11 @@ impl StringOccurrences {
         }
     }
 
-    pub fn get_random_entry(self: &Self, generator: &mut ThreadRng) -> &'static str {
+    pub fn entries(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.entries.iter().map(|item| item.entry)
+    }
+
+    pub fn get_random_entry(&self, generator: &mut ThreadRng) -> &'static str {
         let value = generator.next_u32() % self.total_occurrences;
         let mut counter = 0;
         for item in &self.entries {
@@ -56,3 +56,10 @@ impl StringOccurrences {
         panic!("This should not happen, as counter should always at some point reach the value.");
     }
 }
+
+pub fn parse_column<T: FromStr>(column: &str, line: &str) -> T {
+    let Ok(value) = T::from_str(column) else {
+        panic!("Could not parse column \"{}\" in line \"{}\"", column, line);
+    };
+    value
+}
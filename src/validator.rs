@@ -0,0 +1,532 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Utc};
+
+use crate::io::{
+    read_source_file, PATROLS_OUTPUT_FILE, PLACES_OUTPUT_FILE, POLICEMEN_CSV_OUTPUT_FILE,
+    POLICEMEN_DB_OUTPUT_FILE, POLICEMEN_PATROLS_OUTPUT_FILE, REPORTS_OUTPUT_FILE,
+    VEHICLE_DB_OUTPUT_FILE,
+};
+use crate::source_format::{SourceFormats, ISO_DATETIME_FORMAT, ISO_DATE_FORMAT};
+
+// Dates outside of these years are out of range
+const MIN_VALID_YEAR: i32 = 1900;
+const MAX_VALID_YEAR: i32 = 2050;
+const ID_COLUMN: usize = 0;
+const REPORT_PLACE_COLUMN: usize = 1;
+const REPORT_TIME_COLUMN: usize = 2;
+const PATROL_VEHICLE_COLUMN: usize = 1;
+const PATROL_REPORT_COLUMN: usize = 2;
+const PATROL_SENDING_COLUMN: usize = 3;
+const PATROL_ARRIVAL_COLUMN: usize = 4;
+const PATROL_FINISH_COLUMN: usize = 5;
+const POLICEMAN_EMPLOYMENT_COLUMN: usize = 3;
+const POLICEMAN_RESIGNATION_COLUMN: usize = 7;
+const POLICEMAN_PATROL_POLICEMAN_COLUMN: usize = 0;
+const POLICEMAN_PATROL_PATROL_COLUMN: usize = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub file_name: String,
+    /// The 1-based line of the file, as in the defects manifest.
+    pub row: usize,
+    pub message: String,
+}
+
+struct Table {
+    file_name: String,
+    rows: Vec<Vec<String>>,
+}
+
+/// A patrol lasts until it is finished, unfinished patrols are still going on.
+#[derive(Debug, Copy, Clone)]
+struct PatrolPeriod {
+    row: usize,
+    sending_time: DateTime<Utc>,
+    finish_time: Option<DateTime<Utc>>,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.file_name, self.row, self.message)
+    }
+}
+
+impl Table {
+    fn read(snapshot_name: &str, file_suffix: &str, formats: SourceFormats) -> Self {
+        Self {
+            file_name: snapshot_name.to_string() + file_suffix,
+            rows: read_source_file(snapshot_name, file_suffix, formats),
+        }
+    }
+
+    /// The rows with their 1-based line numbers.
+    fn numbered_rows(&self) -> impl Iterator<Item = (usize, &Vec<String>)> {
+        self.rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| (index + 1, row))
+    }
+
+    fn ids(&self) -> HashSet<&str> {
+        self.rows
+            .iter()
+            .map(|row| row[ID_COLUMN].as_str())
+            .collect()
+    }
+
+    fn rows_by_id(&self) -> HashMap<&str, (usize, &Vec<String>)> {
+        self.numbered_rows()
+            .map(|(row_number, row)| (row[ID_COLUMN].as_str(), (row_number, row)))
+            .collect()
+    }
+
+    fn violation(&self, row: usize, message: String) -> Violation {
+        Violation {
+            file_name: self.file_name.clone(),
+            row,
+            message,
+        }
+    }
+}
+
+/// Reads the source files of a snapshot and checks that they are consistent with each other.
+pub fn validate_snapshot(snapshot_name: &str, formats: SourceFormats) -> Vec<Violation> {
+    let places = Table::read(snapshot_name, PLACES_OUTPUT_FILE, formats);
+    let reports = Table::read(snapshot_name, REPORTS_OUTPUT_FILE, formats);
+    let patrols = Table::read(snapshot_name, PATROLS_OUTPUT_FILE, formats);
+    let vehicles = Table::read(snapshot_name, VEHICLE_DB_OUTPUT_FILE, formats);
+    let policemen = Table::read(snapshot_name, POLICEMEN_DB_OUTPUT_FILE, formats);
+    let policemen_csv = Table::read(snapshot_name, POLICEMEN_CSV_OUTPUT_FILE, formats);
+    let policemen_patrols = Table::read(snapshot_name, POLICEMEN_PATROLS_OUTPUT_FILE, formats);
+
+    let mut violations = vec![];
+    check_foreign_key(
+        &mut violations,
+        &reports,
+        REPORT_PLACE_COLUMN,
+        &places,
+        "place",
+    );
+    check_foreign_key(
+        &mut violations,
+        &patrols,
+        PATROL_REPORT_COLUMN,
+        &reports,
+        "report",
+    );
+    check_foreign_key(
+        &mut violations,
+        &patrols,
+        PATROL_VEHICLE_COLUMN,
+        &vehicles,
+        "vehicle",
+    );
+    check_foreign_key(
+        &mut violations,
+        &policemen_patrols,
+        POLICEMAN_PATROL_POLICEMAN_COLUMN,
+        &policemen,
+        "policeman",
+    );
+    check_foreign_key(
+        &mut violations,
+        &policemen_patrols,
+        POLICEMAN_PATROL_PATROL_COLUMN,
+        &patrols,
+        "patrol",
+    );
+    check_date_ranges(&mut violations, &reports, &[REPORT_TIME_COLUMN]);
+    check_date_ranges(
+        &mut violations,
+        &patrols,
+        &[
+            PATROL_SENDING_COLUMN,
+            PATROL_ARRIVAL_COLUMN,
+            PATROL_FINISH_COLUMN,
+        ],
+    );
+    check_patrol_times(&mut violations, &patrols, &reports);
+    check_vehicle_overlaps(&mut violations, &patrols);
+    check_policemen_patrols(
+        &mut violations,
+        &policemen_patrols,
+        &patrols,
+        &policemen_csv,
+    );
+    violations.sort_by(|first, second| {
+        (&first.file_name, first.row).cmp(&(&second.file_name, second.row))
+    });
+    violations
+}
+
+fn check_foreign_key(
+    violations: &mut Vec<Violation>,
+    table: &Table,
+    column: usize,
+    referenced_table: &Table,
+    referenced_name: &str,
+) {
+    let ids = referenced_table.ids();
+    for (row_number, row) in table.numbered_rows() {
+        let id = &row[column];
+        if !id.is_empty() && !ids.contains(id.as_str()) {
+            violations.push(table.violation(
+                row_number,
+                format!(
+                    "{referenced_name} {id} does not exist in {}",
+                    referenced_table.file_name
+                ),
+            ));
+        }
+    }
+}
+
+/// Values out of range are left out of the other checks.
+fn check_date_ranges(violations: &mut Vec<Violation>, table: &Table, columns: &[usize]) {
+    for (row_number, row) in table.numbered_rows() {
+        for column in columns {
+            let value = &row[*column];
+            if !value.is_empty() && parse_datetime(value).is_none() {
+                violations.push(table.violation(
+                    row_number,
+                    format!("date {value} in column {column} is invalid or out of range"),
+                ));
+            }
+        }
+    }
+}
+
+/// Patrols are sent after their report and arrive before they finish.
+fn check_patrol_times(violations: &mut Vec<Violation>, patrols: &Table, reports: &Table) {
+    let reports_by_id = reports.rows_by_id();
+    for (row_number, row) in patrols.numbered_rows() {
+        let sending_time = parse_datetime(&row[PATROL_SENDING_COLUMN]);
+        let arrival_time = parse_datetime(&row[PATROL_ARRIVAL_COLUMN]);
+        let finish_time = parse_datetime(&row[PATROL_FINISH_COLUMN]);
+
+        let report_time = reports_by_id
+            .get(row[PATROL_REPORT_COLUMN].as_str())
+            .and_then(|(_, report)| parse_datetime(&report[REPORT_TIME_COLUMN]));
+        if let (Some(sending_time), Some(report_time)) = (sending_time, report_time) {
+            if sending_time < report_time {
+                violations.push(patrols.violation(
+                    row_number,
+                    format!("patrol sent at {sending_time} before its report at {report_time}"),
+                ));
+            }
+        }
+        if let (Some(arrival_time), Some(finish_time)) = (arrival_time, finish_time) {
+            if finish_time < arrival_time {
+                violations.push(patrols.violation(
+                    row_number,
+                    format!("patrol finished at {finish_time} before arriving at {arrival_time}"),
+                ));
+            }
+        }
+    }
+}
+
+fn check_vehicle_overlaps(violations: &mut Vec<Violation>, patrols: &Table) {
+    let mut periods_by_vehicle = HashMap::<&str, Vec<PatrolPeriod>>::new();
+    for (row_number, row) in patrols.numbered_rows() {
+        let vehicle_id = row[PATROL_VEHICLE_COLUMN].as_str();
+        if vehicle_id.is_empty() {
+            continue;
+        }
+        if let Some(period) = PatrolPeriod::from_row(row_number, row) {
+            periods_by_vehicle
+                .entry(vehicle_id)
+                .or_default()
+                .push(period);
+        }
+    }
+
+    for (vehicle_id, periods) in periods_by_vehicle {
+        for (period, overlapped_period) in find_overlaps(periods) {
+            violations.push(patrols.violation(
+                period.row,
+                format!(
+                    "vehicle {vehicle_id} is already in the patrol from row {}",
+                    overlapped_period.row
+                ),
+            ));
+        }
+    }
+}
+
+/// Policemen are employed when sent, and are never in two patrols at once.
+fn check_policemen_patrols(
+    violations: &mut Vec<Violation>,
+    policemen_patrols: &Table,
+    patrols: &Table,
+    policemen: &Table,
+) {
+    let patrols_by_id = patrols.rows_by_id();
+    let policemen_by_id = policemen.rows_by_id();
+    let mut periods_by_policeman = HashMap::<&str, Vec<PatrolPeriod>>::new();
+
+    for (row_number, row) in policemen_patrols.numbered_rows() {
+        let policeman_id = row[POLICEMAN_PATROL_POLICEMAN_COLUMN].as_str();
+        let Some(period) = patrols_by_id
+            .get(row[POLICEMAN_PATROL_PATROL_COLUMN].as_str())
+            .and_then(|(patrol_row_number, patrol)| {
+                PatrolPeriod::from_row(*patrol_row_number, patrol)
+            })
+        else {
+            continue;
+        };
+        periods_by_policeman
+            .entry(policeman_id)
+            .or_default()
+            .push(PatrolPeriod {
+                row: row_number,
+                ..period
+            });
+
+        let Some((_, policeman)) = policemen_by_id.get(policeman_id) else {
+            continue;
+        };
+        let sending_date = period.sending_time.date_naive();
+        let employment_date = parse_date(&policeman[POLICEMAN_EMPLOYMENT_COLUMN]);
+        let resignation_date = parse_date(&policeman[POLICEMAN_RESIGNATION_COLUMN]);
+        let is_employed = employment_date.is_none_or(|date| date <= sending_date)
+            && resignation_date.is_none_or(|date| sending_date <= date);
+        if !is_employed {
+            violations.push(policemen_patrols.violation(
+                row_number,
+                format!("policeman {policeman_id} is not employed on {sending_date}"),
+            ));
+        }
+    }
+
+    for (policeman_id, periods) in periods_by_policeman {
+        for (period, overlapped_period) in find_overlaps(periods) {
+            violations.push(policemen_patrols.violation(
+                period.row,
+                format!(
+                    "policeman {policeman_id} is already in the patrol from row {}",
+                    overlapped_period.row
+                ),
+            ));
+        }
+    }
+}
+
+impl PatrolPeriod {
+    /// None if a time is missing or invalid, except for the finish time of an unfinished patrol.
+    fn from_row(row_number: usize, row: &[String]) -> Option<Self> {
+        let finish_time = &row[PATROL_FINISH_COLUMN];
+        Some(Self {
+            row: row_number,
+            sending_time: parse_datetime(&row[PATROL_SENDING_COLUMN])?,
+            finish_time: if finish_time.is_empty() {
+                None
+            } else {
+                Some(parse_datetime(finish_time)?)
+            },
+        })
+    }
+}
+
+/// Each period starting before an earlier one ends, paired with that earlier period.
+fn find_overlaps(mut periods: Vec<PatrolPeriod>) -> Vec<(PatrolPeriod, PatrolPeriod)> {
+    periods.sort_by_key(|period| (period.sending_time, period.row));
+    let mut overlaps = vec![];
+    let mut latest_period: Option<PatrolPeriod> = None;
+    for period in periods {
+        if let Some(latest) = latest_period {
+            if latest
+                .finish_time
+                .is_none_or(|finish_time| period.sending_time < finish_time)
+            {
+                overlaps.push((period, latest));
+            }
+        }
+        let ends_later = latest_period.is_none_or(|latest| match latest.finish_time {
+            None => false,
+            Some(latest_finish) => period
+                .finish_time
+                .is_none_or(|finish_time| finish_time > latest_finish),
+        });
+        if ends_later {
+            latest_period = Some(period);
+        }
+    }
+    overlaps
+}
+
+fn parse_datetime(value: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value, ISO_DATETIME_FORMAT)
+        .ok()
+        .map(|datetime| datetime.and_utc())
+        .filter(|datetime| is_valid_year(datetime.year()))
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, ISO_DATE_FORMAT)
+        .ok()
+        .filter(|date| is_valid_year(date.year()))
+}
+
+fn is_valid_year(year: i32) -> bool {
+    (MIN_VALID_YEAR..=MAX_VALID_YEAR).contains(&year)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use crate::validator::{
+        check_foreign_key, check_patrol_times, check_policemen_patrols, find_overlaps,
+        PatrolPeriod, Table, Violation,
+    };
+
+    fn table(file_name: &str, rows: &[&[&str]]) -> Table {
+        Table {
+            file_name: file_name.to_string(),
+            rows: rows
+                .iter()
+                .map(|row| row.iter().map(|value| value.to_string()).collect())
+                .collect(),
+        }
+    }
+
+    fn rows(violations: &[Violation]) -> Vec<usize> {
+        let mut rows = violations
+            .iter()
+            .map(|violation| violation.row)
+            .collect::<Vec<_>>();
+        rows.sort();
+        rows
+    }
+
+    fn patrols() -> Table {
+        table(
+            "patrols.csv",
+            &[
+                &[
+                    "0",
+                    "0",
+                    "0",
+                    "2023-06-01 12:05:00 UTC",
+                    "2023-06-01 12:20:00 UTC",
+                    "2023-06-01 13:00:00 UTC",
+                ],
+                &[
+                    "1",
+                    "1",
+                    "0",
+                    "2023-06-01 11:00:00 UTC",
+                    "2023-06-01 12:30:00 UTC",
+                    "2023-06-01 14:00:00 UTC",
+                ],
+                &[
+                    "2",
+                    "0",
+                    "0",
+                    "2023-06-01 15:00:00 UTC",
+                    "2023-06-01 15:30:00 UTC",
+                    "2023-06-01 15:10:00 UTC",
+                ],
+                &["3", "1", "0", "2099-06-01 11:00:00 UTC", "", ""],
+            ],
+        )
+    }
+
+    #[test]
+    fn should_find_references_to_missing_rows() {
+        let places = table("places.csv", &[&["0"], &["1"]]);
+        let reports = table("reports.csv", &[&["0", "1"], &["1", "5"], &["2", ""]]);
+        let mut violations = vec![];
+
+        check_foreign_key(&mut violations, &reports, 1, &places, "place");
+
+        assert_eq!(
+            violations,
+            vec![Violation {
+                file_name: "reports.csv".to_string(),
+                row: 2,
+                message: "place 5 does not exist in places.csv".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn should_find_patrols_sent_before_their_report_or_finished_before_arriving() {
+        let reports = table("reports.csv", &[&["0", "0", "2023-06-01 12:00:00 UTC"]]);
+        let mut violations = vec![];
+
+        check_patrol_times(&mut violations, &patrols(), &reports);
+
+        assert_eq!(rows(&violations), vec![2, 3]);
+    }
+
+    #[test]
+    fn should_find_policemen_not_employed_or_in_two_patrols_at_once() {
+        let policeman = |id, employment_date, resignation_date| {
+            [
+                id,
+                "JAN",
+                "KOWALSKI",
+                employment_date,
+                "",
+                "",
+                "",
+                resignation_date,
+            ]
+        };
+        let policemen = table(
+            "policemen.csv",
+            &[
+                &policeman("0", "2020-01-01", ""),
+                &policeman("1", "2023-07-01", ""),
+                &policeman("2", "2020-01-01", "2023-05-31"),
+                &policeman("3", "2099-01-01", ""),
+            ],
+        );
+        let policemen_patrols = table(
+            "policemen_patrols.csv",
+            &[
+                &["0", "0"],
+                &["0", "1"],
+                &["1", "2"],
+                &["2", "2"],
+                &["3", "2"],
+            ],
+        );
+        let mut violations = vec![];
+
+        check_policemen_patrols(&mut violations, &policemen_patrols, &patrols(), &policemen);
+
+        // Patrol 0 starts during patrol 1, and the out of range employment date of policeman 3 is
+        // left out
+        assert_eq!(rows(&violations), vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn should_find_periods_starting_before_an_earlier_one_ends() {
+        let period = |row, start_hour, finish_hour: Option<u32>| PatrolPeriod {
+            row,
+            sending_time: Utc.with_ymd_and_hms(2023, 6, 1, start_hour, 0, 0).unwrap(),
+            finish_time: finish_hour
+                .map(|hour| Utc.with_ymd_and_hms(2023, 6, 1, hour, 0, 0).unwrap()),
+        };
+        let periods = vec![
+            period(1, 8, Some(12)),
+            period(2, 9, Some(10)),
+            period(3, 11, Some(13)),
+            period(4, 13, Some(14)),
+            period(5, 15, None),
+            period(6, 20, Some(21)),
+        ];
+
+        let overlaps = find_overlaps(periods)
+            .iter()
+            .map(|(period, overlapped_period)| (period.row, overlapped_period.row))
+            .collect::<Vec<_>>();
+
+        assert_eq!(overlaps, vec![(2, 1), (3, 1), (6, 5)]);
+    }
+}
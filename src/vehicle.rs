@@ -1,4 +1,8 @@
-use crate::string_occurrences::StringOccurrences;
+use crate::audit::Audit;
+use crate::place::{get_city, Coordinates};
+use crate::station::Station;
+use crate::string_occurrences::parse_column;
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
 use once_cell::sync::Lazy;
 use rand::prelude::{SliceRandom, ThreadRng};
 use rand::Rng;
@@ -8,61 +12,414 @@ use std::rc::Rc;
 const VEHICLE_MODELS_STRING: &str = include_str!("../data/vehicle_models.txt");
 const REGISTRATION_PLATE_CODES_STRING: &str = include_str!("../data/registration_plate_codes.txt");
 
-const MANUFACTURE_YEAR_MIN: u32 = 2005;
-const MANUFACTURE_YEAR_MAX: u32 = 2020;
-const EXPECTED_REGISTRATION_PLATE_LENGTH: usize = 8;
-const DEFAULT_SEAT_COUNT: u32 = 5;
-static REGISTRATION_PLATE_ALLOWED_CHARACTERS: Lazy<Vec<char>> =
-    Lazy::new(|| ('0'..='9').chain('A'..='Z').collect::<Vec<_>>());
+// Vehicles in the initial fleet are up to 8 years old
+const MAX_INITIAL_VEHICLE_AGE_YEARS: u32 = 8;
+// Vehicles are retired during the year they turn 10
+const RETIREMENT_AGE_YEARS: u32 = 10;
+const SECONDS_PER_DAY: f64 = 86400.0;
+// 6 months
+pub const DAYS_BETWEEN_SERVICES: i64 = 182;
+const MIN_SERVICE_HOURS: i64 = 8;
+const MAX_SERVICE_HOURS: i64 = 48;
+// A new vehicle breaks down about once a year, and each year of age makes it 15% more likely
+const NEW_VEHICLE_DAYS_BETWEEN_BREAKDOWNS: f64 = 365.0;
+const BREAKDOWN_RATE_INCREASE_PER_YEAR: f64 = 0.15;
+const MIN_REPAIR_HOURS: i64 = 4;
+const MAX_REPAIR_HOURS: i64 = 240;
+const MIN_PROCUREMENT_DAYS: i64 = 30;
+const MAX_PROCUREMENT_DAYS: i64 = 120;
+// Police cars cover 20 to 40 thousand kilometres a year
+const MIN_YEARLY_DISTANCE_KM: f64 = 20000.0;
+const MAX_YEARLY_DISTANCE_KM: f64 = 40000.0;
+// The vehicle part of a plate with a 3-letter county code, '#' is a digit, '@' a letter placed next
+// to a digit and '*' a letter placed only next to other letters
+const REGISTRATION_PLATE_PATTERNS: [&str; 8] = [
+    "@###", "##@*", "#@##", "#@@#", "*@##", "#####", "####@", "###@*",
+];
+// Letters too similar to digits are never placed next to a digit, where they could be misread
+const REGISTRATION_PLATE_EXCLUDED_LETTERS: [char; 5] = ['B', 'D', 'I', 'O', 'Z'];
+static REGISTRATION_PLATE_LETTERS: Lazy<Vec<char>> = Lazy::new(|| {
+    ('A'..='Z')
+        .filter(|letter| !REGISTRATION_PLATE_EXCLUDED_LETTERS.contains(letter))
+        .collect::<Vec<_>>()
+});
+// The fleet-management system numbers vehicles on its own, e.g. "FM-7K2Q9XWA"
+const FLEET_ID_PREFIX: &str = "FM-";
+const FLEET_ID_LENGTH: usize = 8;
+const FLEET_ID_CHARS: &[u8] = b"0123456789ABCDEFGHJKLMNPQRSTUVWXYZ";
 
-static VEHICLE_MODELS_ENTRIES: Lazy<StringOccurrences> =
-    Lazy::new(|| StringOccurrences::from_str(VEHICLE_MODELS_STRING));
-static REGISTRATION_PLATE_CODES_ENTRIES: Lazy<StringOccurrences> =
-    Lazy::new(|| StringOccurrences::from_str(REGISTRATION_PLATE_CODES_STRING));
+static VEHICLE_MODELS: Lazy<Vec<VehicleModel>> =
+    Lazy::new(|| create_vehicle_models_from_str(VEHICLE_MODELS_STRING));
+static REGISTRATION_PLATE_CODES: Lazy<Vec<(&'static str, &'static str)>> =
+    Lazy::new(|| create_registration_plate_codes_from_str(REGISTRATION_PLATE_CODES_STRING));
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum VehicleState {
     Available,
     Occupied,
+    OutOfService,
+    Decommissioned,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct VehicleModel {
+    pub make: &'static str,
+    pub name: &'static str,
+    /// One of osobowy, terenowy, furgon or motocykl.
+    pub vehicle_type: &'static str,
+    pub fuel_type: &'static str,
+    pub seat_count: usize,
+    /// How often the model appears in the fleet, relative to the other models.
+    pub weight: u32,
+    /// Litres per 100 km, in the urban and extra-urban cycle.
+    pub urban_l_per_100_km: f64,
+    pub extra_urban_l_per_100_km: f64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MaintenanceKind {
+    Service,
+    Breakdown,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Maintenance {
+    pub id: usize,
+    pub vehicle_id: usize,
+    pub kind: MaintenanceKind,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Vehicle {
     pub id: usize,
-    pub model: &'static str,
+    pub station_id: usize,
+    pub model: &'static VehicleModel,
     pub registration_plate: Rc<String>,
+    /// The id of the vehicle in the fleet-management system.
+    pub fleet_id: String,
     pub manufacture_year: u32,
-    pub seat_count: u32,
     pub state: VehicleState,
-    pub vehicle_type: &'static str,
+    /// Where the vehicle finished its last patrol, as it keeps patrolling the area instead of
+    /// driving back to the station.
+    pub position: Coordinates,
+    pub commissioning_date: DateTime<Utc>,
+    pub decommissioning_date: Option<DateTime<Utc>>,
+    pub odometer_km: f64,
+    pub audit: Audit,
 }
 
 impl Vehicle {
-    pub fn generate_with_id(generator: &mut ThreadRng, id: usize) -> Self {
+    /// A vehicle that was already in use before the simulation started.
+    pub fn generate_used_with_id_and_station(
+        generator: &mut ThreadRng,
+        id: usize,
+        station: &Station,
+        commissioning_date: DateTime<Utc>,
+    ) -> Self {
+        let age = generator.gen_range(0..=MAX_INITIAL_VEHICLE_AGE_YEARS);
+        let manufacture_year = commissioning_date.year() as u32 - age;
+        let mut vehicle = Self::generate_with_id_station_and_manufacture_year(
+            generator,
+            id,
+            station,
+            commissioning_date,
+            manufacture_year,
+        );
+        vehicle.odometer_km = (0..age)
+            .map(|_| generator.gen_range(MIN_YEARLY_DISTANCE_KM..MAX_YEARLY_DISTANCE_KM))
+            .fold(0.0, |total, distance_km| total + distance_km);
+        vehicle
+    }
+
+    pub fn generate_new_with_id_and_station(
+        generator: &mut ThreadRng,
+        id: usize,
+        station: &Station,
+        commissioning_date: DateTime<Utc>,
+    ) -> Self {
+        let manufacture_year = commissioning_date.year() as u32;
+        Self::generate_with_id_station_and_manufacture_year(
+            generator,
+            id,
+            station,
+            commissioning_date,
+            manufacture_year,
+        )
+    }
+
+    fn generate_with_id_station_and_manufacture_year(
+        generator: &mut ThreadRng,
+        id: usize,
+        station: &Station,
+        commissioning_date: DateTime<Utc>,
+        manufacture_year: u32,
+    ) -> Self {
         Self {
             id,
-            model: VEHICLE_MODELS_ENTRIES.get_random_entry(generator),
-            registration_plate: Rc::new(generate_registration_plate(generator)),
-            manufacture_year: generator.gen_range(MANUFACTURE_YEAR_MIN..MANUFACTURE_YEAR_MAX),
-            seat_count: DEFAULT_SEAT_COUNT,
+            station_id: station.id,
+            model: VEHICLE_MODELS
+                .choose_weighted(generator, |model| model.weight)
+                .unwrap(),
+            registration_plate: Rc::new(generate_registration_plate(
+                generator,
+                get_city(station.city).county,
+            )),
+            fleet_id: generate_fleet_id(generator),
+            manufacture_year,
             state: VehicleState::Available,
-            vehicle_type: "terenowy",
+            position: station.coordinates,
+            commissioning_date,
+            decommissioning_date: None,
+            odometer_km: 0.0,
+            audit: Audit::generate_with_time(generator, commissioning_date),
+        }
+    }
+
+    /// Whether the vehicle can be sent now with a patrol of the given size.
+    pub fn can_carry_patrol(&self, policemen_count: usize) -> bool {
+        self.state == VehicleState::Available && self.model.seat_count >= policemen_count
+    }
+
+    /// A random day in the year the vehicle reaches the retirement age.
+    pub fn generate_retirement_date(&self, generator: &mut ThreadRng) -> DateTime<Utc> {
+        let retirement_year = (self.manufacture_year + RETIREMENT_AGE_YEARS) as i32;
+        let retirement_date = Utc
+            .with_ymd_and_hms(retirement_year, 1, 1, 0, 0, 0)
+            .unwrap()
+            .checked_add_signed(Duration::days(generator.gen_range(0..365)))
+            .unwrap();
+        retirement_date.max(self.commissioning_date)
+    }
+
+    /// Older vehicles break down more often.
+    pub fn generate_next_breakdown_time(
+        &self,
+        generator: &mut ThreadRng,
+        current_time: DateTime<Utc>,
+    ) -> DateTime<Utc> {
+        let age = current_time.year() as f64 - self.manufacture_year as f64;
+        let mean_days = NEW_VEHICLE_DAYS_BETWEEN_BREAKDOWNS
+            / (1.0 + age.max(0.0) * BREAKDOWN_RATE_INCREASE_PER_YEAR);
+        let uniform: f64 = generator.gen();
+        let days = -(1.0 - uniform).ln() * mean_days;
+        current_time
+            .checked_add_signed(Duration::seconds((days * SECONDS_PER_DAY) as i64))
+            .unwrap()
+    }
+}
+
+impl Maintenance {
+    pub fn generate_with_kind_and_id(
+        generator: &mut ThreadRng,
+        vehicle_id: usize,
+        kind: MaintenanceKind,
+        start_time: DateTime<Utc>,
+        id: usize,
+    ) -> Self {
+        let hours = match kind {
+            MaintenanceKind::Service => generator.gen_range(MIN_SERVICE_HOURS..=MAX_SERVICE_HOURS),
+            MaintenanceKind::Breakdown => generator.gen_range(MIN_REPAIR_HOURS..=MAX_REPAIR_HOURS),
+        };
+
+        Self {
+            id,
+            vehicle_id,
+            kind,
+            start_time,
+            end_time: start_time
+                .checked_add_signed(Duration::hours(hours))
+                .unwrap(),
         }
     }
 }
 
-fn generate_registration_plate(generator: &mut ThreadRng) -> String {
-    let mut registration_plate = String::with_capacity(EXPECTED_REGISTRATION_PLATE_LENGTH);
-    let a = REGISTRATION_PLATE_CODES_ENTRIES.get_random_entry(generator);
-    registration_plate.push_str(a);
+impl VehicleModel {
+    pub fn fuel_used_l(&self, distance_km: f64, is_urban: bool) -> f64 {
+        let l_per_100_km = if is_urban {
+            self.urban_l_per_100_km
+        } else {
+            self.extra_urban_l_per_100_km
+        };
+        distance_km * l_per_100_km / 100.0
+    }
+}
+
+impl MaintenanceKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            MaintenanceKind::Service => "PRZEGLAD",
+            MaintenanceKind::Breakdown => "AWARIA",
+        }
+    }
+}
+
+fn create_vehicle_models_from_str(data: &'static str) -> Vec<VehicleModel> {
+    data.split('\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let columns = line.split(',').collect::<Vec<_>>();
+            let [make, name, vehicle_type, fuel_type, seat_count, weight, urban_l_per_100_km, extra_urban_l_per_100_km] =
+                columns[..]
+            else {
+                panic!(
+                    "Each vehicle model line should have 8 columns. Found line \"{}\"",
+                    line
+                );
+            };
+
+            VehicleModel {
+                make,
+                name,
+                vehicle_type,
+                fuel_type,
+                seat_count: parse_column(seat_count, line),
+                weight: parse_column(weight, line),
+                urban_l_per_100_km: parse_column(urban_l_per_100_km, line),
+                extra_urban_l_per_100_km: parse_column(extra_urban_l_per_100_km, line),
+            }
+        })
+        .collect()
+}
+
+pub fn generate_procurement_time(
+    generator: &mut ThreadRng,
+    current_time: DateTime<Utc>,
+) -> DateTime<Utc> {
+    let days = generator.gen_range(MIN_PROCUREMENT_DAYS..=MAX_PROCUREMENT_DAYS);
+    current_time
+        .checked_add_signed(Duration::days(days))
+        .unwrap()
+}
+
+/// A plate with the code of the county the vehicle is based in.
+fn generate_registration_plate(generator: &mut ThreadRng, county: &str) -> String {
+    let mut registration_plate = get_registration_plate_code(county).to_string();
     registration_plate.push(' ');
 
-    for _ in 0..(EXPECTED_REGISTRATION_PLATE_LENGTH - registration_plate.len()) {
-        let registration_plate_char = REGISTRATION_PLATE_ALLOWED_CHARACTERS
-            .choose(generator)
-            .unwrap();
-        registration_plate.push(*registration_plate_char);
+    for pattern_char in REGISTRATION_PLATE_PATTERNS
+        .choose(generator)
+        .unwrap()
+        .chars()
+    {
+        let registration_plate_char = match pattern_char {
+            '#' => char::from_digit(generator.gen_range(0..10), 10).unwrap(),
+            '@' => *REGISTRATION_PLATE_LETTERS.choose(generator).unwrap(),
+            _ => generator.gen_range('A'..='Z'),
+        };
+        registration_plate.push(registration_plate_char);
     }
 
     registration_plate
 }
+
+fn generate_fleet_id(generator: &mut ThreadRng) -> String {
+    let mut fleet_id = FLEET_ID_PREFIX.to_string();
+    for _ in 0..FLEET_ID_LENGTH {
+        fleet_id.push(*FLEET_ID_CHARS.choose(generator).unwrap() as char);
+    }
+    fleet_id
+}
+
+fn get_registration_plate_code(county: &str) -> &'static str {
+    let Some((_, code)) = REGISTRATION_PLATE_CODES
+        .iter()
+        .find(|(code_county, _)| *code_county == county)
+    else {
+        panic!(
+            "Every county should have its code defined in registration_plate_codes.txt. Missing county \"{}\"",
+            county
+        );
+    };
+    code
+}
+
+fn create_registration_plate_codes_from_str(
+    data: &'static str,
+) -> Vec<(&'static str, &'static str)> {
+    data.split('\n')
+        .filter_map(|line| {
+            let mut line_split = line.split(',');
+            let county = line_split.next()?;
+            let code = line_split.next()?;
+            Some((county, code))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use rand::thread_rng;
+
+    use crate::report::{get_report_type_parameters, get_reported_types};
+    use crate::station::get_all_stations;
+    use crate::vehicle::{
+        generate_registration_plate, Vehicle, VehicleState, REGISTRATION_PLATE_EXCLUDED_LETTERS,
+        VEHICLE_MODELS,
+    };
+
+    #[test]
+    fn should_use_more_fuel_in_urban_traffic_for_every_model() {
+        for model in VEHICLE_MODELS.iter() {
+            assert!(model.fuel_used_l(100.0, true) > model.fuel_used_l(100.0, false));
+        }
+    }
+
+    #[test]
+    fn should_generate_plates_with_the_county_code_and_excluded_letters_only_away_from_digits() {
+        let mut generator = thread_rng();
+        let mut has_excluded_letter = false;
+        for _ in 0..1000 {
+            let registration_plate = generate_registration_plate(&mut generator, "tczewski");
+            let (code, vehicle_part) = registration_plate.split_once(' ').unwrap();
+            let chars = vehicle_part.chars().collect::<Vec<_>>();
+
+            assert_eq!(code, "GTC");
+            assert!([4, 5].contains(&chars.len()));
+            for (position, item) in chars.iter().enumerate() {
+                if !REGISTRATION_PLATE_EXCLUDED_LETTERS.contains(item) {
+                    continue;
+                }
+                has_excluded_letter = true;
+                let neighbours = [position.checked_sub(1), Some(position + 1)];
+                assert!(neighbours
+                    .iter()
+                    .flatten()
+                    .filter_map(|neighbour| chars.get(*neighbour))
+                    .all(|neighbour| !neighbour.is_ascii_digit()));
+            }
+        }
+        assert!(has_excluded_letter);
+    }
+
+    #[test]
+    fn should_carry_only_patrols_fitting_the_seats() {
+        let mut generator = thread_rng();
+        let mut vehicle = Vehicle::generate_new_with_id_and_station(
+            &mut generator,
+            0,
+            get_all_stations().first().unwrap(),
+            Utc.with_ymd_and_hms(2015, 6, 1, 0, 0, 0).unwrap(),
+        );
+        vehicle.model = VEHICLE_MODELS
+            .iter()
+            .find(|model| model.seat_count == 1)
+            .unwrap();
+
+        assert!(vehicle.can_carry_patrol(1));
+        assert!(!vehicle.can_carry_patrol(2));
+        vehicle.state = VehicleState::Occupied;
+        assert!(!vehicle.can_carry_patrol(1));
+    }
+
+    #[test]
+    fn should_have_a_patrol_fitting_every_model() {
+        for model in VEHICLE_MODELS.iter() {
+            assert!(get_reported_types().any(|report_type| {
+                get_report_type_parameters(report_type).policemen_per_patrol <= model.seat_count
+            }));
+        }
+    }
+}
@@ -0,0 +1,457 @@
+use chrono::{DateTime, Timelike, Utc};
+
+use crate::calendar::{date_to_key, Calendar, CalendarDay, TimeOfDay};
+use crate::citizen::CitizenRegistry;
+use crate::outcome::{OutcomeKind, PatrolOutcome, ALL_OUTCOME_KINDS};
+use crate::patrol::Patrol;
+use crate::person::{LastNameChange, Person, Policeman};
+use crate::place::Place;
+use crate::report::{get_all_report_types, Report};
+use crate::station::Station;
+use crate::vehicle::Vehicle;
+
+// Surrogate keys start at 1, so that 0 can be used by the ETL as an "unknown" member
+const FIRST_SURROGATE_KEY: usize = 1;
+
+#[derive(Debug, Copy, Clone)]
+pub struct OfficerDimensionRow {
+    pub key: usize,
+    pub policeman: Policeman,
+    pub last_name: &'static str,
+    pub valid_from: DateTime<Utc>,
+    pub valid_to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VehicleDimensionRow {
+    pub key: usize,
+    pub vehicle: Vehicle,
+    pub valid_from: DateTime<Utc>,
+    pub valid_to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct StationDimensionRow {
+    pub key: usize,
+    pub station: Station,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct PlaceDimensionRow {
+    pub key: usize,
+    pub place: Place,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct OutcomeDimensionRow {
+    pub key: usize,
+    pub kind: OutcomeKind,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ReporterDimensionRow {
+    pub key: usize,
+    pub person: Person,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ReportTypeDimensionRow {
+    pub key: usize,
+    pub name: &'static str,
+}
+
+#[derive(Debug, Clone)]
+pub struct PatrolFactRow {
+    pub patrol_id: usize,
+    pub report_id: usize,
+    pub report_date_key: u32,
+    pub report_time_key: u32,
+    pub sending_date_key: u32,
+    pub sending_time_key: u32,
+    pub place_key: usize,
+    pub reporter_key: usize,
+    pub report_type_key: usize,
+    pub vehicle_key: usize,
+    pub responsible_station_key: usize,
+    pub sending_station_key: usize,
+    pub officer_keys: Vec<usize>,
+    pub dispatch_delay_seconds: i64,
+    pub queue_wait_seconds: i64,
+    pub distance_km: f64,
+    pub fuel_used_l: f64,
+    pub travel_seconds: Option<i64>,
+    pub on_scene_seconds: Option<i64>,
+    pub response_seconds: Option<i64>,
+    /// Known only once the patrol has finished.
+    pub outcome_key: Option<usize>,
+    pub fine_amount: Option<u32>,
+}
+
+/// The star schema that an ETL process should produce out of a single snapshot, computed directly
+/// from the simulation state instead of the exported source files.
+#[derive(Debug, Clone)]
+pub struct Warehouse {
+    pub officers: Vec<OfficerDimensionRow>,
+    pub vehicles: Vec<VehicleDimensionRow>,
+    pub stations: Vec<StationDimensionRow>,
+    pub places: Vec<PlaceDimensionRow>,
+    pub reporters: Vec<ReporterDimensionRow>,
+    pub outcomes: Vec<OutcomeDimensionRow>,
+    pub report_types: Vec<ReportTypeDimensionRow>,
+    pub dates: Vec<CalendarDay>,
+    pub times: Vec<TimeOfDay>,
+    pub patrols: Vec<PatrolFactRow>,
+}
+
+impl Warehouse {
+    #[allow(clippy::too_many_arguments)]
+    pub fn build(
+        snapshot_date: DateTime<Utc>,
+        calendar: &Calendar,
+        stations: &[Station],
+        places: &[Place],
+        policemen: &[Policeman],
+        last_name_changes: &[LastNameChange],
+        vehicles: &[Vehicle],
+        citizens: &CitizenRegistry,
+        reports: &[Report],
+        patrols: &[Patrol],
+        patrol_outcomes: &[PatrolOutcome],
+    ) -> Self {
+        let officers = build_officer_dimension(policemen, last_name_changes, snapshot_date);
+        let vehicles = build_vehicle_dimension(vehicles);
+        let stations = stations
+            .iter()
+            .enumerate()
+            .map(|(index, station)| StationDimensionRow {
+                key: FIRST_SURROGATE_KEY + index,
+                station: *station,
+            })
+            .collect::<Vec<_>>();
+        let places = places
+            .iter()
+            .enumerate()
+            .map(|(index, place)| PlaceDimensionRow {
+                key: FIRST_SURROGATE_KEY + index,
+                place: *place,
+            })
+            .collect::<Vec<_>>();
+        let reporters = build_reporter_dimension(citizens, reports, snapshot_date);
+        let outcomes = ALL_OUTCOME_KINDS
+            .iter()
+            .enumerate()
+            .map(|(index, kind)| OutcomeDimensionRow {
+                key: FIRST_SURROGATE_KEY + index,
+                kind: *kind,
+            })
+            .collect::<Vec<_>>();
+        let mut outcomes_by_patrol = vec![None; patrols.len()];
+        for outcome in patrol_outcomes {
+            outcomes_by_patrol[outcome.patrol_id] = Some(outcome);
+        }
+        let report_types = get_all_report_types()
+            .enumerate()
+            .map(|(index, name)| ReportTypeDimensionRow {
+                key: FIRST_SURROGATE_KEY + index,
+                name,
+            })
+            .collect::<Vec<_>>();
+        let dates = calendar.days.clone();
+        let times = calendar.times.clone();
+
+        // Late-arriving patrols and reports are loaded by the snapshot in which they are recorded
+        let patrols = patrols
+            .iter()
+            .filter(|patrol| {
+                patrol.audit.created_at < snapshot_date
+                    && reports[patrol.report_id].audit.created_at < snapshot_date
+            })
+            .map(|patrol| {
+                let report = reports.get(patrol.report_id).unwrap();
+                let officer_keys = patrol
+                    .policemen_ids
+                    .iter()
+                    .map(|policeman_id| {
+                        find_officer_key(&officers, *policeman_id, patrol.sending_time)
+                    })
+                    .collect();
+                let seconds_if_happened = |from: DateTime<Utc>, to: DateTime<Utc>| {
+                    (to < snapshot_date).then(|| (to - from).num_seconds())
+                };
+
+                PatrolFactRow {
+                    patrol_id: patrol.id,
+                    report_id: report.id,
+                    report_date_key: date_key(report.time),
+                    report_time_key: time_key(report.time),
+                    sending_date_key: date_key(patrol.sending_time),
+                    sending_time_key: time_key(patrol.sending_time),
+                    reporter_key: find_reporter_key(&reporters, report.reporter_id),
+                    place_key: places
+                        .iter()
+                        .find(|row| row.place.id == report.recorded_place_id())
+                        .unwrap()
+                        .key,
+                    report_type_key: report_types
+                        .iter()
+                        .find(|row| row.name == report.recorded_report_type())
+                        .unwrap()
+                        .key,
+                    vehicle_key: find_vehicle_key(
+                        &vehicles,
+                        patrol.vehicle_id,
+                        patrol.sending_time,
+                    ),
+                    responsible_station_key: find_station_key(&stations, report.station_id),
+                    sending_station_key: find_station_key(&stations, patrol.station_id),
+                    officer_keys,
+                    dispatch_delay_seconds: (patrol.sending_time - report.time).num_seconds(),
+                    queue_wait_seconds: (patrol.sending_time - patrol.queued_time).num_seconds(),
+                    distance_km: patrol.distance_km,
+                    fuel_used_l: patrol.fuel_used_l,
+                    travel_seconds: seconds_if_happened(patrol.sending_time, patrol.arrival_time),
+                    on_scene_seconds: seconds_if_happened(patrol.arrival_time, patrol.finish_time),
+                    response_seconds: seconds_if_happened(report.time, patrol.arrival_time),
+                    outcome_key: outcomes_by_patrol[patrol.id].map(|outcome| {
+                        outcomes
+                            .iter()
+                            .find(|row| row.kind == outcome.kind)
+                            .unwrap()
+                            .key
+                    }),
+                    fine_amount: outcomes_by_patrol[patrol.id]
+                        .and_then(|outcome| outcome.fine_amount),
+                }
+            })
+            .collect();
+
+        Self {
+            officers,
+            vehicles,
+            stations,
+            places,
+            reporters,
+            outcomes,
+            report_types,
+            dates,
+            times,
+            patrols,
+        }
+    }
+}
+
+pub fn date_key(time: DateTime<Utc>) -> u32 {
+    date_to_key(time.date_naive())
+}
+
+pub fn time_key(time: DateTime<Utc>) -> u32 {
+    time.hour() * 100 + time.minute()
+}
+
+/// Every last name change creates a new version of the officer, valid from the moment of the
+/// change. The first version is valid since the employment date.
+fn build_officer_dimension(
+    policemen: &[Policeman],
+    last_name_changes: &[LastNameChange],
+    snapshot_date: DateTime<Utc>,
+) -> Vec<OfficerDimensionRow> {
+    let mut rows = vec![];
+    for policeman in policemen {
+        let mut changes = last_name_changes
+            .iter()
+            .filter(|change| change.person_id == policeman.person.id)
+            .filter(|change| change.time < snapshot_date)
+            .collect::<Vec<_>>();
+        changes.sort_by_key(|change| change.time);
+
+        let mut last_name = changes
+            .first()
+            .map(|change| change.previous_last_name)
+            .unwrap_or(policeman.person.last_name);
+        let mut valid_from = policeman.employment_date;
+        for change in changes {
+            rows.push(OfficerDimensionRow {
+                key: FIRST_SURROGATE_KEY + rows.len(),
+                policeman: *policeman,
+                last_name,
+                valid_from,
+                valid_to: Some(change.time),
+            });
+            last_name = change.new_last_name;
+            valid_from = change.time;
+        }
+
+        rows.push(OfficerDimensionRow {
+            key: FIRST_SURROGATE_KEY + rows.len(),
+            policeman: *policeman,
+            last_name,
+            valid_from,
+            valid_to: None,
+        });
+    }
+
+    rows
+}
+
+/// Vehicles never change their station, registration plate nor any other described attribute, so
+/// the dimension is of type 1 with a single version of each vehicle, valid from its
+/// commissioning to its decommissioning. Availability changes with every patrol and maintenance,
+/// which belongs in the facts rather than in the dimension.
+fn build_vehicle_dimension(vehicles: &[Vehicle]) -> Vec<VehicleDimensionRow> {
+    vehicles
+        .iter()
+        .enumerate()
+        .map(|(index, vehicle)| VehicleDimensionRow {
+            key: FIRST_SURROGATE_KEY + index,
+            vehicle: vehicle.clone(),
+            valid_from: vehicle.commissioning_date,
+            valid_to: vehicle.decommissioning_date,
+        })
+        .collect()
+}
+
+/// Only the citizens who reported something recorded before the snapshot, ordered by their id.
+fn build_reporter_dimension(
+    citizens: &CitizenRegistry,
+    reports: &[Report],
+    snapshot_date: DateTime<Utc>,
+) -> Vec<ReporterDimensionRow> {
+    let mut reporter_ids = reports
+        .iter()
+        .filter(|report| report.audit.created_at < snapshot_date)
+        .map(|report| report.reporter_id)
+        .collect::<Vec<_>>();
+    reporter_ids.sort_unstable();
+    reporter_ids.dedup();
+
+    // Citizen ids are consecutive, so the reporter can be found by index
+    reporter_ids
+        .iter()
+        .enumerate()
+        .map(|(index, reporter_id)| ReporterDimensionRow {
+            key: FIRST_SURROGATE_KEY + index,
+            person: citizens.citizens()[*reporter_id],
+        })
+        .collect()
+}
+
+fn find_reporter_key(reporters: &[ReporterDimensionRow], reporter_id: usize) -> usize {
+    let Ok(index) = reporters.binary_search_by_key(&reporter_id, |row| row.person.id) else {
+        panic!(
+            "Every reporter of a loaded report should be in the reporter dimension. Missing reporter {}",
+            reporter_id
+        );
+    };
+    reporters[index].key
+}
+
+fn find_station_key(stations: &[StationDimensionRow], station_id: usize) -> usize {
+    stations
+        .iter()
+        .find(|row| row.station.id == station_id)
+        .unwrap()
+        .key
+}
+
+fn find_officer_key(
+    officers: &[OfficerDimensionRow],
+    policeman_id: usize,
+    time: DateTime<Utc>,
+) -> usize {
+    officers
+        .iter()
+        .filter(|row| row.policeman.person.id == policeman_id)
+        .find(|row| row.valid_to.is_none_or(|valid_to| time < valid_to))
+        .unwrap()
+        .key
+}
+
+fn find_vehicle_key(
+    vehicles: &[VehicleDimensionRow],
+    vehicle_id: usize,
+    time: DateTime<Utc>,
+) -> usize {
+    vehicles
+        .iter()
+        .filter(|row| row.vehicle.id == vehicle_id)
+        .find(|row| row.valid_to.is_none_or(|valid_to| time < valid_to))
+        .unwrap()
+        .key
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use rand::thread_rng;
+
+    use crate::citizen::CitizenRegistry;
+    use crate::person::Policeman;
+    use crate::place::get_all_places;
+    use crate::report::Report;
+    use crate::warehouse::{build_officer_dimension, build_reporter_dimension};
+
+    #[test]
+    fn should_create_new_officer_version_for_each_last_name_change() {
+        let mut generator = thread_rng();
+        let employment_date = Utc.with_ymd_and_hms(2015, 6, 1, 0, 0, 0).unwrap();
+        let first_change_date = Utc.with_ymd_and_hms(2016, 1, 1, 0, 0, 0).unwrap();
+        let second_change_date = Utc.with_ymd_and_hms(2017, 1, 1, 0, 0, 0).unwrap();
+        let snapshot_date = Utc.with_ymd_and_hms(2018, 1, 1, 0, 0, 0).unwrap();
+        let mut policeman = Policeman::generate_just_employed_with_id_and_station(
+            &mut generator,
+            &employment_date,
+            0,
+            0,
+        );
+        let original_last_name = policeman.person.last_name;
+        let changes = [
+            policeman
+                .person
+                .change_to_random_surname(&mut generator, first_change_date),
+            policeman
+                .person
+                .change_to_random_surname(&mut generator, second_change_date),
+        ];
+
+        let rows = build_officer_dimension(&[policeman], &changes, snapshot_date);
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].last_name, original_last_name);
+        assert_eq!(rows[0].valid_to, Some(first_change_date));
+        assert_eq!(rows[1].valid_from, first_change_date);
+        assert_eq!(rows[2].last_name, policeman.person.last_name);
+        assert_eq!(rows[2].valid_to, None);
+    }
+
+    #[test]
+    fn should_include_only_citizens_who_reported_before_the_snapshot() {
+        let mut generator = thread_rng();
+        let time = Utc.with_ymd_and_hms(2023, 6, 5, 12, 0, 0).unwrap();
+        let mut citizens = CitizenRegistry::default();
+        let offender_id = citizens.choose_offender(&mut generator, time);
+        let reports = (0..3)
+            .map(|id| {
+                let reporter_id = citizens.add_citizen(&mut generator, time);
+                let mut report = Report::generate_with_time_reporter_and_id(
+                    &mut generator,
+                    time,
+                    get_all_places().len(),
+                    citizens.get(reporter_id),
+                    id,
+                );
+                report.audit.created_at = time + Duration::hours(id as i64);
+                report
+            })
+            .collect::<Vec<_>>();
+
+        let rows = build_reporter_dimension(&citizens, &reports, time + Duration::minutes(90));
+
+        let reporter_ids = rows.iter().map(|row| row.person.id).collect::<Vec<_>>();
+        assert_eq!(
+            reporter_ids,
+            [reports[0].reporter_id, reports[1].reporter_id]
+        );
+        assert!(!reporter_ids.contains(&offender_id));
+        assert_eq!(rows[1].key, rows[0].key + 1);
+    }
+}